@@ -35,7 +35,7 @@ struct RevocationConfig {
 }
 
 impl RevocationConfig {
-    pub fn as_ref_config(&self) -> Result<CredentialRevocationConfig> {
+    pub fn as_ref_config(&self) -> Result<CredentialRevocationConfig<'_>> {
         Ok(CredentialRevocationConfig {
             reg_def: self.reg_def.cast_ref()?,
             reg_def_private: self.reg_def_private.cast_ref()?,
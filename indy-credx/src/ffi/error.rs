@@ -22,6 +22,9 @@ pub enum ErrorCode {
     InvalidUserRevocId = 6,
     ProofRejected = 7,
     RevocationRegistryFull = 8,
+    InvalidTailsFile = 9,
+    Cancelled = 10,
+    InvalidNonce = 11,
 }
 
 impl From<ErrorKind> for ErrorCode {
@@ -35,6 +38,9 @@ impl From<ErrorKind> for ErrorCode {
             ErrorKind::InvalidUserRevocId => ErrorCode::InvalidUserRevocId,
             ErrorKind::ProofRejected => ErrorCode::ProofRejected,
             ErrorKind::RevocationRegistryFull => ErrorCode::RevocationRegistryFull,
+            ErrorKind::InvalidTailsFile => ErrorCode::InvalidTailsFile,
+            ErrorKind::Cancelled => ErrorCode::Cancelled,
+            ErrorKind::InvalidNonce => ErrorCode::InvalidNonce,
         }
     }
 }
@@ -130,7 +130,7 @@ impl IndyObject {
 
 impl PartialEq for IndyObject {
     fn eq(&self, other: &IndyObject) -> bool {
-        #[allow(clippy::vtable_address_comparisons)]
+        #[allow(ambiguous_wide_pointer_comparisons)]
         // this is allowed only because we create all such objects
         // in one place (the `new` method) and ensure they are not
         // zero-sized.
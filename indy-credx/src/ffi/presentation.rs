@@ -9,7 +9,7 @@ use super::util::{FfiList, FfiStrList};
 use crate::error::Result;
 use crate::services::{
     prover::create_presentation,
-    types::{PresentCredentials, Presentation, RevocationRegistryDefinition},
+    types::{PresentCredentials, Presentation, RevocationRegistryDefinition, VerificationPolicy},
     verifier::_verify_presentation,
 };
 
@@ -184,16 +184,18 @@ pub extern "C" fn credx_verify_presentation(
     rev_reg_entries: FfiList<FfiRevocationEntry>,
     result_p: *mut i8,
 ) -> ErrorCode {
-    _credx_verify_presentation(
-        presentation,
-        pres_req,
-        schemas,
-        cred_defs,
-        rev_reg_defs,
-        rev_reg_entries,
-        false,
-        result_p,
-    )
+    catch_error(|| {
+        _credx_verify_presentation(
+            presentation,
+            pres_req,
+            schemas,
+            cred_defs,
+            rev_reg_defs,
+            rev_reg_entries,
+            &VerificationPolicy::basic(),
+            result_p,
+        )
+    })
 }
 
 #[no_mangle]
@@ -206,57 +208,149 @@ pub extern "C" fn credx_verify_presentation_legacy(
     rev_reg_entries: FfiList<FfiRevocationEntry>,
     result_p: *mut i8,
 ) -> ErrorCode {
-    _credx_verify_presentation(
-        presentation,
-        pres_req,
-        schemas,
-        cred_defs,
-        rev_reg_defs,
-        rev_reg_entries,
-        true,
-        result_p,
-    )
+    catch_error(|| {
+        _credx_verify_presentation(
+            presentation,
+            pres_req,
+            schemas,
+            cred_defs,
+            rev_reg_defs,
+            rev_reg_entries,
+            &VerificationPolicy::legacy(),
+            result_p,
+        )
+    })
 }
 
+#[no_mangle]
 #[allow(clippy::too_many_arguments)]
-fn _credx_verify_presentation(
+pub extern "C" fn credx_verify_presentation_with_policy(
     presentation: ObjectHandle,
     pres_req: ObjectHandle,
     schemas: FfiList<ObjectHandle>,
     cred_defs: FfiList<ObjectHandle>,
     rev_reg_defs: FfiList<ObjectHandle>,
     rev_reg_entries: FfiList<FfiRevocationEntry>,
-    accept_legacy_revocation: bool,
+    policy_json: FfiStr,
     result_p: *mut i8,
 ) -> ErrorCode {
     catch_error(|| {
-        let schemas = IndyObjectList::load(schemas.as_slice()?)?;
-        let cred_defs = IndyObjectList::load(cred_defs.as_slice()?)?;
-        let rev_reg_defs = IndyObjectList::load(rev_reg_defs.as_slice()?)?;
-        let rev_reg_entries = rev_reg_entries.try_collect(|entry| entry.load())?;
-        let mut rev_regs = HashMap::new();
-        for (idx, entry, timestamp) in rev_reg_entries.iter() {
-            if *idx > rev_reg_defs.len() {
-                return Err(err_msg!("Invalid revocation registry entry index"));
+        let policy = match policy_json.as_opt_str() {
+            Some(json) => {
+                serde_json::from_str(json).map_err(err_map!("Invalid verification policy"))?
             }
-            let id = rev_reg_defs[*idx]
-                .cast_ref::<RevocationRegistryDefinition>()?
-                .get_id();
-            rev_regs
-                .entry(id)
-                .or_insert_with(HashMap::new)
-                .insert(*timestamp, entry.cast_ref()?);
-        }
-        let verify = _verify_presentation(
-            presentation.load()?.cast_ref()?,
-            pres_req.load()?.cast_ref()?,
-            &schemas.refs_map()?,
-            &cred_defs.refs_map()?,
-            Some(&rev_reg_defs.refs_map()?),
-            Some(&rev_regs),
-            accept_legacy_revocation,
-        )?;
-        unsafe { *result_p = verify as i8 };
-        Ok(())
+            None => VerificationPolicy::default(),
+        };
+        _credx_verify_presentation(
+            presentation,
+            pres_req,
+            schemas,
+            cred_defs,
+            rev_reg_defs,
+            rev_reg_entries,
+            &policy,
+            result_p,
+        )
     })
 }
+
+#[allow(clippy::too_many_arguments)]
+fn _credx_verify_presentation(
+    presentation: ObjectHandle,
+    pres_req: ObjectHandle,
+    schemas: FfiList<ObjectHandle>,
+    cred_defs: FfiList<ObjectHandle>,
+    rev_reg_defs: FfiList<ObjectHandle>,
+    rev_reg_entries: FfiList<FfiRevocationEntry>,
+    policy: &VerificationPolicy,
+    result_p: *mut i8,
+) -> Result<()> {
+    let schemas = IndyObjectList::load(schemas.as_slice()?)?;
+    let cred_defs = IndyObjectList::load(cred_defs.as_slice()?)?;
+    let rev_reg_defs = IndyObjectList::load(rev_reg_defs.as_slice()?)?;
+    let rev_reg_entries = rev_reg_entries.try_collect(|entry| entry.load())?;
+    let mut rev_regs = HashMap::new();
+    for (idx, entry, timestamp) in rev_reg_entries.iter() {
+        if *idx > rev_reg_defs.len() {
+            return Err(err_msg!("Invalid revocation registry entry index"));
+        }
+        let id = rev_reg_defs[*idx]
+            .cast_ref::<RevocationRegistryDefinition>()?
+            .get_id();
+        rev_regs
+            .entry(id)
+            .or_insert_with(HashMap::new)
+            .insert(*timestamp, entry.cast_ref()?);
+    }
+    let verify = _verify_presentation(
+        presentation.load()?.cast_ref()?,
+        pres_req.load()?.cast_ref()?,
+        &schemas.refs_map()?,
+        &cred_defs.refs_map()?,
+        Some(&rev_reg_defs.refs_map()?),
+        Some(&rev_regs),
+        policy,
+    )?;
+    unsafe { *result_p = verify as i8 };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+    use crate::services::types::PresentationRequest;
+
+    #[test]
+    fn verify_presentation_with_policy_rejects_unknown_keys() {
+        let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+            "nonce": "1234567890",
+            "name": "pres_req_1",
+            "version": "0.1",
+            "requested_attributes": {"attr1_referent": {"name": "phone"}},
+            "requested_predicates": {}
+        }))
+        .unwrap();
+        let presentation: Presentation = serde_json::from_value(serde_json::json!({
+            "proof": {"proofs": [], "aggregated_proof": {"c_hash": "1", "c_list": []}},
+            "requested_proof": {
+                "revealed_attrs": {},
+                "self_attested_attrs": {"attr1_referent": "8-800-300"}
+            },
+            "identifiers": []
+        }))
+        .unwrap();
+        let presentation = ObjectHandle::create(presentation).unwrap();
+        let pres_req = ObjectHandle::create(pres_req).unwrap();
+
+        let verify = |policy: &str| {
+            let policy = CString::new(policy).unwrap();
+            let mut result = -1;
+            let code = credx_verify_presentation_with_policy(
+                presentation,
+                pres_req,
+                FfiList::from(&[][..]),
+                FfiList::from(&[][..]),
+                FfiList::from(&[][..]),
+                FfiList::from(&[][..]),
+                FfiStr::from_cstr(&policy),
+                &mut result,
+            );
+            (code, result)
+        };
+
+        assert_eq!(
+            verify(r#"{"forbid_self_attested": false}"#),
+            (ErrorCode::Success, 0)
+        );
+        assert_eq!(
+            verify(r#"{"forbid_self_attested": true}"#),
+            (ErrorCode::Success, 0)
+        );
+        assert_eq!(
+            verify(r#"{"forbid_self_attestation": true}"#),
+            (ErrorCode::Input, -1)
+        );
+    }
+}
@@ -9,14 +9,17 @@ use indy_data_types::Qualifiable;
 use super::error::{catch_error, ErrorCode};
 use super::object::{IndyObject, IndyObjectId, ObjectHandle};
 use super::util::FfiList;
+use crate::anoncreds_clsignatures::{
+    Error as ClError, ErrorKind as ClErrorKind, RevocationTailsAccessor, Tail,
+};
 use crate::error::Result;
 use crate::services::{
     issuer::{
         create_revocation_registry, merge_revocation_registry_deltas, revoke_credential,
-        update_revocation_registry,
+        unrevoke_credential, update_revocation_registry,
     },
     prover::create_or_update_revocation_state,
-    tails::{TailsFileReader, TailsFileWriter},
+    tails::{verify_tails_file, CancellationToken, TailsFileReader, TailsFileWriter},
     types::{
         CredentialRevocationState, DidValue, IssuanceType, RegistryType, RevocationRegistry,
         RevocationRegistryDefinition, RevocationRegistryDefinitionPrivate, RevocationRegistryDelta,
@@ -39,53 +42,132 @@ pub extern "C" fn credx_create_revocation_registry(
     reg_init_delta_p: *mut ObjectHandle,
 ) -> ErrorCode {
     catch_error(|| {
-        check_useful_c_ptr!(reg_def_p);
-        check_useful_c_ptr!(reg_def_private_p);
-        check_useful_c_ptr!(reg_entry_p);
-        check_useful_c_ptr!(reg_init_delta_p);
-        let origin_did = {
-            let did = origin_did
-                .as_opt_str()
-                .ok_or_else(|| err_msg!("Missing origin DID"))?;
-            DidValue::from_str(did)?
-        };
-        let tag = tag.as_opt_str().ok_or_else(|| err_msg!("Missing tag"))?;
-        let rev_reg_type = {
-            let rtype = rev_reg_type
-                .as_opt_str()
-                .ok_or_else(|| err_msg!("Missing registry type"))?;
-            RegistryType::from_str(rtype).map_err(err_map!(Input))?
-        };
-        let issuance_type = match issuance_type.as_opt_str() {
-            Some(s) => IssuanceType::from_str(s).map_err(err_map!(Input))?,
-            None => IssuanceType::default(),
-        };
-        let mut tails_writer = TailsFileWriter::new(tails_dir_path.into_opt_string());
-        let (reg_def, reg_def_private, reg_entry, reg_init_delta) = create_revocation_registry(
-            &origin_did,
-            cred_def.load()?.cast_ref()?,
+        let tails_writer = TailsFileWriter::new(tails_dir_path.into_opt_string());
+        _create_revocation_registry(
+            origin_did,
+            cred_def,
             tag,
             rev_reg_type,
             issuance_type,
-            max_cred_num
-                .try_into()
-                .map_err(|_| err_msg!("Invalid maximum credential count"))?,
-            &mut tails_writer,
-        )?;
-        let reg_def = ObjectHandle::create(reg_def)?;
-        let reg_def_private = ObjectHandle::create(reg_def_private)?;
-        let reg_entry = ObjectHandle::create(reg_entry)?;
-        let reg_init_delta = ObjectHandle::create(reg_init_delta)?;
-        unsafe {
-            *reg_def_p = reg_def;
-            *reg_def_private_p = reg_def_private;
-            *reg_entry_p = reg_entry;
-            *reg_init_delta_p = reg_init_delta;
-        };
-        Ok(())
+            max_cred_num,
+            tails_writer,
+            reg_def_p,
+            reg_def_private_p,
+            reg_entry_p,
+            reg_init_delta_p,
+        )
     })
 }
 
+/// Callback used to report progress during tails generation.
+///
+/// The callback receives the number of tails written and the total number of
+/// tails. It is called at most once per hundredth of the tails, and always
+/// once all of the tails are written. Returning a non-zero value cancels the
+/// registry creation.
+pub type TailsProgressCallback = extern "C" fn(cb_id: i64, written: i64, total: i64) -> i64;
+
+#[no_mangle]
+pub extern "C" fn credx_create_revocation_registry_with_progress(
+    origin_did: FfiStr,
+    cred_def: ObjectHandle,
+    tag: FfiStr,
+    rev_reg_type: FfiStr,
+    issuance_type: FfiStr,
+    max_cred_num: i64,
+    tails_dir_path: FfiStr,
+    progress_cb: Option<TailsProgressCallback>,
+    progress_cb_id: i64,
+    reg_def_p: *mut ObjectHandle,
+    reg_def_private_p: *mut ObjectHandle,
+    reg_entry_p: *mut ObjectHandle,
+    reg_init_delta_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        let progress_cb = progress_cb.ok_or_else(|| err_msg!("Missing progress callback"))?;
+        let cancel = CancellationToken::new();
+        let tails_writer = TailsFileWriter::new(tails_dir_path.into_opt_string())
+            .with_cancellation(cancel.clone())
+            .with_progress(move |written, total| {
+                if progress_cb(progress_cb_id, written as i64, total as i64) != 0 {
+                    cancel.cancel();
+                }
+            });
+        _create_revocation_registry(
+            origin_did,
+            cred_def,
+            tag,
+            rev_reg_type,
+            issuance_type,
+            max_cred_num,
+            tails_writer,
+            reg_def_p,
+            reg_def_private_p,
+            reg_entry_p,
+            reg_init_delta_p,
+        )
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn _create_revocation_registry(
+    origin_did: FfiStr,
+    cred_def: ObjectHandle,
+    tag: FfiStr,
+    rev_reg_type: FfiStr,
+    issuance_type: FfiStr,
+    max_cred_num: i64,
+    mut tails_writer: TailsFileWriter,
+    reg_def_p: *mut ObjectHandle,
+    reg_def_private_p: *mut ObjectHandle,
+    reg_entry_p: *mut ObjectHandle,
+    reg_init_delta_p: *mut ObjectHandle,
+) -> Result<()> {
+    check_useful_c_ptr!(reg_def_p);
+    check_useful_c_ptr!(reg_def_private_p);
+    check_useful_c_ptr!(reg_entry_p);
+    check_useful_c_ptr!(reg_init_delta_p);
+    let origin_did = {
+        let did = origin_did
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing origin DID"))?;
+        DidValue::from_str(did)?
+    };
+    let tag = tag.as_opt_str().ok_or_else(|| err_msg!("Missing tag"))?;
+    let rev_reg_type = {
+        let rtype = rev_reg_type
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing registry type"))?;
+        RegistryType::from_str(rtype).map_err(err_map!(Input))?
+    };
+    let issuance_type = match issuance_type.as_opt_str() {
+        Some(s) => IssuanceType::from_str(s).map_err(err_map!(Input))?,
+        None => IssuanceType::default(),
+    };
+    let (reg_def, reg_def_private, reg_entry, reg_init_delta) = create_revocation_registry(
+        &origin_did,
+        cred_def.load()?.cast_ref()?,
+        tag,
+        rev_reg_type,
+        issuance_type,
+        max_cred_num
+            .try_into()
+            .map_err(|_| err_msg!("Invalid maximum credential count"))?,
+        &mut tails_writer,
+    )?;
+    let reg_def = ObjectHandle::create(reg_def)?;
+    let reg_def_private = ObjectHandle::create(reg_def_private)?;
+    let reg_entry = ObjectHandle::create(reg_entry)?;
+    let reg_init_delta = ObjectHandle::create(reg_init_delta)?;
+    unsafe {
+        *reg_def_p = reg_def;
+        *reg_def_private_p = reg_def_private;
+        *reg_entry_p = reg_entry;
+        *reg_init_delta_p = reg_init_delta;
+    };
+    Ok(())
+}
+
 #[no_mangle]
 pub extern "C" fn credx_update_revocation_registry(
     cred_def: ObjectHandle,
@@ -152,6 +234,44 @@ pub extern "C" fn credx_revoke_credential(
     })
 }
 
+#[no_mangle]
+pub extern "C" fn credx_unrevoke_credential(
+    cred_def: ObjectHandle,
+    rev_reg_def: ObjectHandle,
+    rev_reg_def_priv: ObjectHandle,
+    rev_reg: ObjectHandle,
+    cred_rev_idx: i64,
+    issued: FfiList<i64>,
+    revoked: FfiList<i64>,
+    rev_reg_p: *mut ObjectHandle,
+    rev_reg_delta_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(rev_reg_p);
+        check_useful_c_ptr!(rev_reg_delta_p);
+        let issued = registry_indices_to_set(issued.as_slice()?.iter().cloned())?;
+        let revoked = registry_indices_to_set(revoked.as_slice()?.iter().cloned())?;
+        let (rev_reg, rev_reg_delta) = unrevoke_credential(
+            cred_def.load()?.cast_ref()?,
+            rev_reg_def.load()?.cast_ref()?,
+            rev_reg_def_priv.load()?.cast_ref()?,
+            rev_reg.load()?.cast_ref()?,
+            cred_rev_idx
+                .try_into()
+                .map_err(|_| err_msg!("Invalid registry index"))?,
+            &issued,
+            &revoked,
+        )?;
+        let rev_reg = ObjectHandle::create(rev_reg)?;
+        let rev_reg_delta = ObjectHandle::create(rev_reg_delta)?;
+        unsafe {
+            *rev_reg_p = rev_reg;
+            *rev_reg_delta_p = rev_reg_delta;
+        };
+        Ok(())
+    })
+}
+
 fn registry_indices_to_set(indices: impl Iterator<Item = i64>) -> Result<BTreeSet<u32>> {
     indices.into_iter().try_fold(BTreeSet::new(), |mut r, idx| {
         r.insert(
@@ -212,6 +332,19 @@ pub extern "C" fn credx_revocation_registry_definition_get_attribute(
     })
 }
 
+#[no_mangle]
+pub extern "C" fn credx_verify_tails_file(
+    rev_reg_def: ObjectHandle,
+    tails_path: FfiStr,
+) -> ErrorCode {
+    catch_error(|| {
+        let tails_path = tails_path
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing tails file path"))?;
+        verify_tails_file(tails_path, rev_reg_def.load()?.cast_ref()?)
+    })
+}
+
 impl_indy_object!(
     RevocationRegistryDefinitionPrivate,
     "RevocationRegistryDefinitionPrivate"
@@ -262,32 +395,195 @@ pub extern "C" fn credx_create_or_update_revocation_state(
 ) -> ErrorCode {
     catch_error(|| {
         check_useful_c_ptr!(rev_state_p);
-        let prev_rev_state = rev_state.opt_load()?;
         let tails_reader = TailsFileReader::new(
             tails_path
                 .as_opt_str()
                 .ok_or_else(|| err_msg!("Missing tails file path"))?,
         );
-        let rev_state = create_or_update_revocation_state(
-            tails_reader,
-            rev_reg_def.load()?.cast_ref()?,
-            rev_reg_delta.load()?.cast_ref()?,
-            rev_reg_index
-                .try_into()
-                .map_err(|_| err_msg!("Invalid credential revocation index"))?,
-            timestamp
-                .try_into()
-                .map_err(|_| err_msg!("Invalid timestamp"))?,
-            prev_rev_state
-                .as_ref()
-                .map(IndyObject::cast_ref)
-                .transpose()?,
+        let rev_state = _create_or_update_revocation_state(
+            &tails_reader,
+            rev_reg_def,
+            rev_reg_delta,
+            rev_reg_index,
+            timestamp,
+            rev_state,
         )?;
-        let rev_state = ObjectHandle::create(rev_state)?;
         unsafe { *rev_state_p = rev_state };
         Ok(())
     })
 }
 
+/// Callback used to fetch an individual tail from the host application.
+///
+/// The callback must write the tail identified by `tail_id` into the buffer
+/// at `tail_p` (`tail_len` bytes) and return zero, or return a non-zero value
+/// if the tail could not be retrieved.
+pub type TailsAccessorCallback =
+    extern "C" fn(cb_id: i64, tail_id: i64, tail_p: *mut u8, tail_len: i64) -> i64;
+
+struct FfiTailsAccessor {
+    cb: TailsAccessorCallback,
+    cb_id: i64,
+}
+
+impl RevocationTailsAccessor for FfiTailsAccessor {
+    fn access_tail(
+        &self,
+        tail_id: u32,
+        accessor: &mut dyn FnMut(&Tail),
+    ) -> std::result::Result<(), ClError> {
+        let mut tail_bytes = [0u8; Tail::BYTES_REPR_SIZE];
+        let result = (self.cb)(
+            self.cb_id,
+            tail_id as i64,
+            tail_bytes.as_mut_ptr(),
+            tail_bytes.len() as i64,
+        );
+        if result != 0 {
+            return Err(ClError::new(
+                ClErrorKind::InvalidState,
+                format!("Tails accessor callback failed for tail {tail_id}: {result}"),
+            ));
+        }
+        let tail = Tail::from_bytes(&tail_bytes)?;
+        accessor(&tail);
+        Ok(())
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn credx_create_or_update_revocation_state_with_accessor(
+    rev_reg_def: ObjectHandle,
+    rev_reg_delta: ObjectHandle,
+    rev_reg_index: i64,
+    timestamp: i64,
+    tails_cb: Option<TailsAccessorCallback>,
+    tails_cb_id: i64,
+    rev_state: ObjectHandle,
+    rev_state_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(rev_state_p);
+        let tails_accessor = FfiTailsAccessor {
+            cb: tails_cb.ok_or_else(|| err_msg!("Missing tails accessor callback"))?,
+            cb_id: tails_cb_id,
+        };
+        let rev_state = _create_or_update_revocation_state(
+            &tails_accessor,
+            rev_reg_def,
+            rev_reg_delta,
+            rev_reg_index,
+            timestamp,
+            rev_state,
+        )?;
+        unsafe { *rev_state_p = rev_state };
+        Ok(())
+    })
+}
+
+fn _create_or_update_revocation_state(
+    tails_reader: &dyn RevocationTailsAccessor,
+    rev_reg_def: ObjectHandle,
+    rev_reg_delta: ObjectHandle,
+    rev_reg_index: i64,
+    timestamp: i64,
+    rev_state: ObjectHandle,
+) -> Result<ObjectHandle> {
+    let prev_rev_state = rev_state.opt_load()?;
+    let rev_state = create_or_update_revocation_state(
+        tails_reader,
+        rev_reg_def.load()?.cast_ref()?,
+        rev_reg_delta.load()?.cast_ref()?,
+        rev_reg_index
+            .try_into()
+            .map_err(|_| err_msg!("Invalid credential revocation index"))?,
+        timestamp
+            .try_into()
+            .map_err(|_| err_msg!("Invalid timestamp"))?,
+        prev_rev_state
+            .as_ref()
+            .map(IndyObject::cast_ref)
+            .transpose()?,
+    )?;
+    ObjectHandle::create(rev_state)
+}
+
 impl_indy_object!(CredentialRevocationState, "CredentialRevocationState");
 impl_indy_object_from_json!(CredentialRevocationState, credx_revocation_state_from_json);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::prover;
+    use crate::services::tails::TailsBufferReader;
+    use crate::services::test_support::{create_registry, TestRegistry};
+
+    // The callback id is the address of the tails buffer
+    extern "C" fn read_tail(cb_id: i64, tail_id: i64, tail_p: *mut u8, tail_len: i64) -> i64 {
+        let tails = unsafe { &*(cb_id as usize as *const Vec<u8>) };
+        let start = 2 + (tail_id * tail_len) as usize;
+        match tails.get(start..start + tail_len as usize) {
+            Some(tail) => {
+                unsafe { std::ptr::copy_nonoverlapping(tail.as_ptr(), tail_p, tail.len()) };
+                0
+            }
+            None => 1,
+        }
+    }
+
+    extern "C" fn fail_tail(_cb_id: i64, _tail_id: i64, _tail_p: *mut u8, _tail_len: i64) -> i64 {
+        5
+    }
+
+    #[test]
+    fn revocation_state_with_accessor_callback() {
+        let TestRegistry {
+            rev_reg_def,
+            initial_delta,
+            tails,
+            ..
+        } = create_registry(IssuanceType::ISSUANCE_BY_DEFAULT);
+
+        let reader = TailsBufferReader::new(tails.clone()).unwrap();
+        let accessor: &dyn RevocationTailsAccessor = &reader;
+        let expected = prover::create_or_update_revocation_state(
+            accessor,
+            &rev_reg_def,
+            &initial_delta,
+            2,
+            10,
+            None,
+        )
+        .unwrap();
+
+        let rev_reg_def = ObjectHandle::create(rev_reg_def).unwrap();
+        let initial_delta = ObjectHandle::create(initial_delta).unwrap();
+        let create_state = |cb: TailsAccessorCallback| {
+            let mut rev_state = ObjectHandle(0);
+            let code = credx_create_or_update_revocation_state_with_accessor(
+                rev_reg_def,
+                initial_delta,
+                2,
+                10,
+                Some(cb),
+                &tails as *const Vec<u8> as usize as i64,
+                ObjectHandle(0),
+                &mut rev_state,
+            );
+            (code, rev_state)
+        };
+
+        let (code, rev_state) = create_state(read_tail);
+        assert_eq!(code, ErrorCode::Success);
+        let rev_state = rev_state.load().unwrap();
+        let rev_state: &CredentialRevocationState = rev_state.cast_ref().unwrap();
+        assert_eq!(
+            serde_json::to_value(rev_state).unwrap(),
+            serde_json::to_value(&expected).unwrap()
+        );
+
+        let (code, rev_state) = create_state(fail_tail);
+        assert_eq!(code, ErrorCode::InvalidState);
+        assert_eq!(rev_state, ObjectHandle(0));
+    }
+}
@@ -19,7 +19,7 @@ impl<'a, T> FfiList<'a, T> {
         if self.data.is_null() || self.count == 0 {
             Ok(&[])
         } else if self.count < 0 {
-            return Err(err_msg!(Input, "Invalid index for result set"));
+            Err(err_msg!(Input, "Invalid index for result set"))
         } else {
             Ok(unsafe { slice::from_raw_parts(self.data, self.count as usize) })
         }
@@ -45,6 +45,16 @@ impl<'a, T> FfiList<'a, T> {
     }
 }
 
+impl<'a, T> From<&'a [T]> for FfiList<'a, T> {
+    fn from(items: &'a [T]) -> Self {
+        Self {
+            count: items.len() as i64,
+            data: items.as_ptr(),
+            _pd: PhantomData,
+        }
+    }
+}
+
 pub type FfiStrList<'a> = FfiList<'a, FfiStr<'a>>;
 
 impl<'a> FfiStrList<'a> {
@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::types::*;
+use crate::error::Error;
+
+/// The held credentials which may satisfy each referent of a presentation
+/// request, as found by `prover::match_credentials`. Candidates are indices
+/// into the list of credentials which was searched.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CredentialMatches {
+    pub attributes: HashMap<String, Vec<usize>>,
+    pub predicates: HashMap<String, Vec<usize>>,
+}
+
+impl CredentialMatches {
+    /// The referents which are not satisfied by any credential.
+    pub fn unmatched(&self) -> Vec<&str> {
+        let mut referents: Vec<&str> = self
+            .attributes
+            .iter()
+            .chain(self.predicates.iter())
+            .filter(|(_, candidates)| candidates.is_empty())
+            .map(|(referent, _)| referent.as_str())
+            .collect();
+        referents.sort_unstable();
+        referents
+    }
+
+    /// Select the first candidate for each referent, revealing all requested
+    /// attributes. Unmatched referents are left out of the selection.
+    pub fn select_first(&self) -> CredentialSelection {
+        CredentialSelection {
+            attributes: self
+                .attributes
+                .iter()
+                .filter_map(|(referent, candidates)| {
+                    candidates
+                        .first()
+                        .map(|idx| (referent.clone(), (*idx, true)))
+                })
+                .collect(),
+            predicates: self
+                .predicates
+                .iter()
+                .filter_map(|(referent, candidates)| {
+                    candidates.first().map(|idx| (referent.clone(), *idx))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The credential chosen for each referent of a presentation request, given as
+/// an index into a list of credentials. Requested attributes also indicate
+/// whether the attribute values are revealed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CredentialSelection {
+    pub attributes: HashMap<String, (usize, bool)>,
+    pub predicates: HashMap<String, usize>,
+}
+
+impl CredentialSelection {
+    /// Build the `PresentCredentials` for this selection. Revocation states
+    /// and their timestamps are given for credential indices which require
+    /// a non-revocation proof.
+    pub fn present_credentials<'p>(
+        &self,
+        credentials: &[&'p Credential],
+        rev_states: &HashMap<usize, (u64, &'p CredentialRevocationState)>,
+    ) -> Result<PresentCredentials<'p>, Error> {
+        let mut selected = BTreeMap::<usize, (Vec<(&str, bool)>, Vec<&str>)>::new();
+        for (referent, (idx, revealed)) in self.attributes.iter() {
+            selected
+                .entry(*idx)
+                .or_default()
+                .0
+                .push((referent.as_str(), *revealed));
+        }
+        for (referent, idx) in self.predicates.iter() {
+            selected.entry(*idx).or_default().1.push(referent.as_str());
+        }
+
+        let mut present = PresentCredentials::new();
+        for (idx, (attributes, predicates)) in selected {
+            let cred = credentials
+                .get(idx)
+                .ok_or_else(|| err_msg!("Selected credential index {} is out of range", idx))?;
+            let (timestamp, rev_state) = match rev_states.get(&idx) {
+                Some((timestamp, rev_state)) => (Some(*timestamp), Some(*rev_state)),
+                None => (None, None),
+            };
+            let mut add = present.add_credential(cred, timestamp, rev_state);
+            for (referent, revealed) in attributes {
+                add.add_requested_attribute(referent, revealed);
+            }
+            for referent in predicates {
+                add.add_requested_predicate(referent);
+            }
+        }
+        Ok(present)
+    }
+}
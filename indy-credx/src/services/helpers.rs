@@ -1,21 +1,23 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
 
 use indy_data_types::anoncreds::{
-    credential::AttributeValues,
+    credential::{AttributeValues, CredentialValues},
     nonce::Nonce,
     pres_request::{AttributeInfo, NonRevocedInterval, PredicateInfo},
+    rev_reg::RevocationRegistryDelta,
+    schema::Schema,
 };
 
+pub use indy_data_types::anoncreds::schema::{attr_common_view, LINK_SECRET_ATTRIBUTE};
+
 use crate::anoncreds_clsignatures::{
-    hash_credential_attribute, CredentialSchema, CredentialValues as ClCredentialValues,
-    Issuer as ClIssuer, LinkSecret as ClLinkSecret, NonCredentialSchema, SubProofRequest,
-    Verifier as ClVerifier,
+    hash_credential_attribute, Accumulator, CredentialSchema,
+    CredentialValues as ClCredentialValues, Issuer as ClIssuer, LinkSecret as ClLinkSecret,
+    NonCredentialSchema, SubProofRequest, Verifier as ClVerifier,
 };
 use crate::error::Result;
-
-pub fn attr_common_view(attr: &str) -> String {
-    attr.replace(' ', "").to_lowercase()
-}
+use crate::services::types::MakeCredentialValues;
 
 pub fn build_credential_schema(attrs: &HashSet<String>) -> Result<CredentialSchema> {
     trace!("build_credential_schema >>> attrs: {:?}", attrs);
@@ -75,6 +77,299 @@ pub fn encode_credential_attribute(raw_value: &str) -> Result<String> {
     }
 }
 
+/// Collect the normalized attribute names signed under a credential
+/// definition for `schema`: the schema attributes and the link secret.
+pub fn schema_attributes(schema: &Schema) -> HashSet<String> {
+    let Schema::SchemaV1(schema) = schema;
+    schema
+        .attr_names
+        .0
+        .iter()
+        .map(|name| attr_common_view(name))
+        .chain([LINK_SECRET_ATTRIBUTE.to_string()])
+        .collect()
+}
+
+/// Check that each encoded value is the encoding of its raw value under the
+/// `AttributeEncoding` in `encodings`, or the `Default` encoding for attributes
+/// not listed, and that no two attribute names are equivalent.
+pub fn check_credential_encodings(
+    cred_values: &CredentialValues,
+    encodings: &HashMap<String, AttributeEncoding>,
+) -> Result<()> {
+    let mut names: Vec<&String> = cred_values.0.keys().collect();
+    names.sort();
+    let mut seen = HashMap::with_capacity(names.len());
+    for name in names {
+        if let Some(prev) = seen.insert(attr_common_view(name), name) {
+            return Err(err_msg!(
+                "Credential attributes {:?} and {:?} are equivalent",
+                prev,
+                name
+            ));
+        }
+        let values = &cred_values.0[name];
+        let encoding = encodings.get(name).copied().unwrap_or_default();
+        let expected = encoding.encode(&values.raw).map_err(err_map!(
+            "Raw value for credential attribute {:?} cannot be encoded as {:?}",
+            name,
+            encoding
+        ))?;
+        if expected != values.encoded {
+            return Err(err_msg!(
+                "Encoded value for credential attribute {:?} does not match its raw value",
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check that credential values provide exactly the normalized attribute names
+/// `attr_names` other than the link secret, as returned by `schema_attributes`,
+/// along with the checks of `check_credential_encodings`.
+pub fn check_credential_values(
+    attr_names: &HashSet<String>,
+    cred_values: &MakeCredentialValues,
+) -> Result<()> {
+    check_credential_encodings(&cred_values.values, &cred_values.encodings)?;
+    let mut provided = HashSet::with_capacity(cred_values.values.0.len());
+    let mut names: Vec<&String> = cred_values.values.0.keys().collect();
+    names.sort();
+    for name in names {
+        let common = attr_common_view(name);
+        if common == LINK_SECRET_ATTRIBUTE {
+            return Err(err_msg!(
+                "Credential attribute {:?} is reserved for the link secret",
+                name
+            ));
+        }
+        if !attr_names.contains(&common) {
+            return Err(err_msg!(
+                "Credential attribute {:?} is not defined by the schema",
+                name
+            ));
+        }
+        provided.insert(common);
+    }
+    let mut missing: Vec<&String> = attr_names
+        .iter()
+        .filter(|name| name.as_str() != LINK_SECRET_ATTRIBUTE && !provided.contains(*name))
+        .collect();
+    missing.sort();
+    if let Some(name) = missing.first() {
+        return Err(err_msg!(
+            "Missing value for credential attribute {:?}",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// An encoding for credential attribute values.
+///
+/// Apart from `Default`, each encoding produces a 32-bit integer which
+/// preserves the ordering of the raw values, so that predicates may be
+/// applied to the attribute. The raw value can be recovered with `decode`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AttributeEncoding {
+    /// 32-bit integers are used directly, other values are hashed.
+    #[default]
+    Default,
+    /// A calendar date in `YYYY-MM-DD` format, encoded as the number of days
+    /// since 1970-01-01.
+    Date,
+    /// `true` or `false`, encoded as 1 or 0.
+    Boolean,
+    /// A decimal number with at most `scale` fractional digits, encoded as a
+    /// multiple of 10^-scale.
+    Decimal { scale: u32 },
+    /// An integer in the range `min..=max`, encoded as its offset from `min`.
+    Integer { min: i64, max: i64 },
+}
+
+impl AttributeEncoding {
+    /// Encode a raw attribute value.
+    pub fn encode(&self, raw_value: &str) -> Result<String> {
+        match self {
+            Self::Default => encode_credential_attribute(raw_value),
+            _ => Ok(self.encode_value(raw_value)?.to_string()),
+        }
+    }
+
+    /// Recover the raw value from an encoded attribute value. Hashed values
+    /// produced by the `Default` encoding cannot be decoded.
+    pub fn decode(&self, encoded: &str) -> Result<String> {
+        let value = encoded.parse::<i32>().map_err(err_map!(
+            "Encoded value is not a 32-bit integer: {}",
+            encoded
+        ))?;
+        match *self {
+            Self::Default => Ok(value.to_string()),
+            Self::Date => {
+                let (year, month, day) = civil_from_days(value as i64);
+                Ok(format!("{:04}-{:02}-{:02}", year, month, day))
+            }
+            Self::Boolean => match value {
+                0 => Ok("false".to_string()),
+                1 => Ok("true".to_string()),
+                _ => Err(err_msg!("Invalid encoded boolean value: {}", value)),
+            },
+            Self::Decimal { scale } => {
+                let factor = decimal_factor(scale)?;
+                let sign = if value < 0 { "-" } else { "" };
+                let value = (value as i64).unsigned_abs();
+                if scale == 0 {
+                    Ok(format!("{}{}", sign, value))
+                } else {
+                    Ok(format!(
+                        "{}{}.{:0width$}",
+                        sign,
+                        value / factor as u64,
+                        value % factor as u64,
+                        width = scale as usize
+                    ))
+                }
+            }
+            Self::Integer { min, max } => {
+                check_integer_range(min, max)?;
+                match min.checked_add(value as i64) {
+                    Some(value) if value >= min && value <= max => Ok(value.to_string()),
+                    _ => Err(err_msg!("Encoded value out of range: {}", value)),
+                }
+            }
+        }
+    }
+
+    /// Encode the value of a predicate on an attribute using this encoding.
+    pub fn encode_predicate_value(&self, raw_value: &str) -> Result<i32> {
+        self.encode_value(raw_value)
+    }
+
+    fn encode_value(&self, raw_value: &str) -> Result<i32> {
+        match *self {
+            Self::Default => raw_value
+                .parse::<i32>()
+                .map_err(err_map!("Value is not a 32-bit integer: {}", raw_value)),
+            Self::Date => {
+                let days = parse_date(raw_value)
+                    .ok_or_else(|| err_msg!("Invalid date value: {}", raw_value))?;
+                i32::try_from(days).map_err(err_map!("Date out of range: {}", raw_value))
+            }
+            Self::Boolean => match raw_value.to_ascii_lowercase().as_str() {
+                "true" => Ok(1),
+                "false" => Ok(0),
+                _ => Err(err_msg!("Invalid boolean value: {}", raw_value)),
+            },
+            Self::Decimal { scale } => {
+                let value = parse_decimal(raw_value, scale)
+                    .ok_or_else(|| err_msg!("Invalid decimal value: {}", raw_value))?;
+                i32::try_from(value).map_err(err_map!("Decimal out of range: {}", raw_value))
+            }
+            Self::Integer { min, max } => {
+                check_integer_range(min, max)?;
+                let value = raw_value
+                    .parse::<i64>()
+                    .map_err(err_map!("Invalid integer value: {}", raw_value))?;
+                if value < min || value > max {
+                    return Err(err_msg!(
+                        "Integer value {} is outside the range {}..={}",
+                        value,
+                        min,
+                        max
+                    ));
+                }
+                Ok((value as i128 - min as i128) as i32)
+            }
+        }
+    }
+}
+
+fn check_integer_range(min: i64, max: i64) -> Result<()> {
+    if min > max || max as i128 - min as i128 > i32::MAX as i128 {
+        return Err(err_msg!(
+            "Integer range {}..={} cannot be encoded as a 32-bit integer",
+            min,
+            max
+        ));
+    }
+    Ok(())
+}
+
+fn decimal_factor(scale: u32) -> Result<i64> {
+    10i64
+        .checked_pow(scale)
+        .filter(|factor| *factor <= i32::MAX as i64)
+        .ok_or_else(|| err_msg!("Unsupported decimal scale: {}", scale))
+}
+
+fn parse_decimal(value: &str, scale: u32) -> Option<i64> {
+    let factor = decimal_factor(scale).ok()?;
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+    if int_part.is_empty()
+        || frac_part.len() > scale as usize
+        || !int_part.bytes().all(|c| c.is_ascii_digit())
+        || !frac_part.bytes().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let frac = format!("{:0<width$}", frac_part, width = scale as usize);
+    let mut result = int_part.parse::<i64>().ok()?.checked_mul(factor)?;
+    if !frac.is_empty() {
+        result = result.checked_add(frac.parse::<i64>().ok()?)?;
+    }
+    Some(if negative { -result } else { result })
+}
+
+fn parse_date(value: &str) -> Option<i64> {
+    let mut parts = value.splitn(3, '-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let year = year.parse::<u32>().ok()? as i64;
+    let month = month.parse::<u32>().ok()?;
+    let day = day.parse::<u32>().ok()?;
+    if !(1..=12).contains(&month) || day == 0 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    // reject days which overflow into the following month
+    if civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+    Some(days)
+}
+
+// Conversions between proleptic Gregorian dates and days since 1970-01-01,
+// following http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = if days >= 0 { days } else { days - 146096 } / 146097;
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
 pub fn build_sub_proof_request(
     attrs_for_credential: &[AttributeInfo],
     predicates_for_credential: &[PredicateInfo],
@@ -143,6 +438,39 @@ pub fn get_non_revoc_interval(
     interval
 }
 
+/// The contents of a revocation registry delta, which the signature library
+/// only exposes through serialization.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RevocationRegistryDeltaParts {
+    #[serde(default)]
+    pub prev_accum: Option<Accumulator>,
+    pub accum: Accumulator,
+    #[serde(default)]
+    pub issued: BTreeSet<u32>,
+    #[serde(default)]
+    pub revoked: BTreeSet<u32>,
+}
+
+pub(crate) fn revocation_registry_delta_parts(
+    delta: &RevocationRegistryDelta,
+) -> Result<RevocationRegistryDeltaParts> {
+    let RevocationRegistryDelta::RevocationRegistryDeltaV1(delta) = delta;
+    serde_json::to_value(&delta.value)
+        .and_then(serde_json::from_value)
+        .map_err(err_map!(
+            Unexpected,
+            "Error reading revocation registry delta"
+        ))
+}
+
+/// Lock a mutex guarding a cache or a record map. The guarded state is only
+/// changed by single insertions and removals, so it remains consistent if
+/// another thread panicked while holding the lock.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
 pub fn new_nonce() -> Result<Nonce> {
     Nonce::new().map_err(err_map!(Unexpected))
 }
@@ -158,6 +486,135 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_check_credential_values() {
+        let attr_names: HashSet<String> = [
+            "firstname".to_string(),
+            "age".to_string(),
+            LINK_SECRET_ATTRIBUTE.to_string(),
+        ]
+        .into();
+        let make_values = |values: &[(&str, &str, &str)]| {
+            let mut cred_values = MakeCredentialValues::default();
+            for (name, raw, encoded) in values {
+                cred_values.add_encoded(*name, *raw, encoded.to_string());
+            }
+            cred_values
+        };
+        let alex = encode_credential_attribute("Alex").unwrap();
+
+        check_credential_values(
+            &attr_names,
+            &make_values(&[("First Name", "Alex", &alex), ("age", "28", "28")]),
+        )
+        .unwrap();
+        // typed encodings are checked against the encoding they were created with
+        let mut typed = make_values(&[("firstname", "Alex", &alex)]);
+        typed
+            .add_typed("age", "1995-06-30", AttributeEncoding::Date)
+            .unwrap();
+        check_credential_values(&attr_names, &typed).unwrap();
+
+        for (values, attr) in [
+            (vec![("firstname", "Alex", alex.as_str())], "age"),
+            (
+                vec![
+                    ("firstname", "Alex", &alex),
+                    ("age", "28", "28"),
+                    ("sex", "male", "1"),
+                ],
+                "sex",
+            ),
+            (
+                vec![("firstname", "Alex", &alex), ("age", "28", "29")],
+                "age",
+            ),
+            (
+                vec![("firstname", "Alexa", &alex), ("age", "28", "28")],
+                "firstname",
+            ),
+            // an integer encoding of a value which is not an integer
+            (
+                vec![("firstname", "Alex", "1"), ("age", "28", "28")],
+                "firstname",
+            ),
+            // a typed encoding without the encoding being recorded
+            (
+                vec![("firstname", "Alex", &alex), ("age", "1995-06-30", "9311")],
+                "age",
+            ),
+            (
+                vec![
+                    ("firstname", "Alex", &alex),
+                    ("First Name", "Alex", &alex),
+                    ("age", "28", "28"),
+                ],
+                "firstname",
+            ),
+            (
+                vec![
+                    ("firstname", "Alex", &alex),
+                    ("age", "28", "28"),
+                    (LINK_SECRET_ATTRIBUTE, "1", "1"),
+                ],
+                LINK_SECRET_ATTRIBUTE,
+            ),
+        ] {
+            let err = check_credential_values(&attr_names, &make_values(&values)).unwrap_err();
+            assert!(
+                err.to_string().contains(&format!("{:?}", attr)),
+                "{} does not mention {}",
+                err,
+                attr
+            );
+        }
+    }
+
+    #[test]
+    fn test_typed_attribute_encoding() {
+        let date = AttributeEncoding::Date;
+        assert_eq!(date.encode("1970-01-01").unwrap(), "0");
+        assert_eq!(date.encode("2000-03-01").unwrap(), "11017");
+        assert_eq!(date.encode("1969-12-31").unwrap(), "-1");
+        assert_eq!(date.decode("11017").unwrap(), "2000-03-01");
+        assert_eq!(date.decode("-1").unwrap(), "1969-12-31");
+        assert!(date.encode("2023-02-29").is_err());
+        assert!(date.encode("2023-2-1").is_err());
+
+        let boolean = AttributeEncoding::Boolean;
+        assert_eq!(boolean.encode("True").unwrap(), "1");
+        assert_eq!(boolean.decode("0").unwrap(), "false");
+        assert!(boolean.encode("yes").is_err());
+
+        let decimal = AttributeEncoding::Decimal { scale: 2 };
+        assert_eq!(decimal.encode("12.5").unwrap(), "1250");
+        assert_eq!(decimal.encode("-0.05").unwrap(), "-5");
+        assert_eq!(decimal.decode("-5").unwrap(), "-0.05");
+        assert_eq!(decimal.decode("1250").unwrap(), "12.50");
+        assert!(decimal.encode("1.234").is_err());
+        assert!(decimal.encode("30000000").is_err());
+
+        let integer = AttributeEncoding::Integer {
+            min: 5_000_000_000,
+            max: 6_000_000_000,
+        };
+        assert_eq!(integer.encode("5000000123").unwrap(), "123");
+        assert_eq!(integer.decode("123").unwrap(), "5000000123");
+        assert_eq!(integer.encode_predicate_value("5000000100").unwrap(), 100);
+        assert!(integer.encode("4999999999").is_err());
+        assert!(AttributeEncoding::Integer {
+            min: i64::MIN,
+            max: 0
+        }
+        .encode("0")
+        .is_err());
+
+        assert_eq!(
+            AttributeEncoding::Default.encode("87121").unwrap(),
+            encode_credential_attribute("87121").unwrap()
+        );
+    }
+
     #[test]
     fn test_encode_attribute() {
         assert_eq!(
@@ -1,7 +1,10 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use super::types::*;
-use crate::anoncreds_clsignatures::Issuer as ClIssuer;
+use crate::anoncreds_clsignatures::{
+    CredentialPublicKey, Issuer as ClIssuer, RevocationRegistry as CryptoRevocationRegistry,
+    RevocationRegistryDelta as CryptoRevocationRegistryDelta,
+};
 use crate::error::Result;
 use crate::services::helpers::*;
 use indy_data_types::anoncreds::{
@@ -29,6 +32,7 @@ pub fn create_schema(
         origin_did, schema_name, schema_version, attr_names);
 
     origin_did.validate()?;
+    attr_names.validate()?;
     let schema_id = SchemaId::new(origin_did, schema_name, schema_version);
     let schema = SchemaV1 {
         id: schema_id,
@@ -301,6 +305,11 @@ pub fn create_credential_offer(
     Ok(credential_offer)
 }
 
+/// Issue a credential for a credential request.
+///
+/// Each encoded value must be the `Default` encoding of its raw value. Values
+/// with typed encodings are issued with `create_credential_for_schema`, which
+/// also checks the attribute names against the schema.
 pub fn create_credential(
     cred_def: &CredentialDefinition,
     cred_def_private: &CredentialDefinitionPrivate,
@@ -312,17 +321,278 @@ pub fn create_credential(
     Credential,
     Option<RevocationRegistry>,
     Option<RevocationRegistryDelta>,
+)> {
+    check_credential_request(cred_offer, cred_request)?;
+    check_credential_encodings(&cred_values, &HashMap::new())?;
+    _create_credential(
+        cred_def,
+        cred_def_private,
+        cred_offer,
+        cred_request,
+        cred_values,
+        revocation_config,
+    )
+}
+
+/// Issue a credential for a credential request after checking that the
+/// credential values provide exactly the attributes of `schema`, and that each
+/// encoded value is the encoding of its raw value under the `AttributeEncoding`
+/// it was added with.
+pub fn create_credential_for_schema(
+    schema: &Schema,
+    cred_def: &CredentialDefinition,
+    cred_def_private: &CredentialDefinitionPrivate,
+    cred_offer: &CredentialOffer,
+    cred_request: &CredentialRequest,
+    cred_values: MakeCredentialValues,
+    revocation_config: Option<CredentialRevocationConfig>,
+) -> Result<(
+    Credential,
+    Option<RevocationRegistry>,
+    Option<RevocationRegistryDelta>,
+)> {
+    if schema.id().to_unqualified() != cred_offer.schema_id.to_unqualified() {
+        return Err(err_msg!(
+            "Credential offer is for schema {} but the schema is {}",
+            cred_offer.schema_id,
+            schema.id()
+        ));
+    }
+    check_credential_request(cred_offer, cred_request)?;
+    check_credential_values(&schema_attributes(schema), &cred_values)?;
+    _create_credential(
+        cred_def,
+        cred_def_private,
+        cred_offer,
+        cred_request,
+        cred_values.into(),
+        revocation_config,
+    )
+}
+
+fn _create_credential(
+    cred_def: &CredentialDefinition,
+    cred_def_private: &CredentialDefinitionPrivate,
+    cred_offer: &CredentialOffer,
+    cred_request: &CredentialRequest,
+    cred_values: CredentialValues,
+    revocation_config: Option<CredentialRevocationConfig>,
+) -> Result<(
+    Credential,
+    Option<RevocationRegistry>,
+    Option<RevocationRegistryDelta>,
 )> {
     trace!("create_credential >>> cred_def: {:?}, cred_def_private: {:?}, cred_offer.nonce: {:?}, cred_request: {:?},\
             cred_values: {:?}, revocation_config: {:?}",
             cred_def, secret!(&cred_def_private), &cred_offer.nonce, &cred_request, secret!(&cred_values), revocation_config,
             );
 
-    let cred_rev_pub_key = match cred_def {
+    let cred_pub_key = match cred_def {
+        CredentialDefinition::CredentialDefinitionV1(cd) => cd.get_public_key().map_err(
+            err_map!(Input, "Credential definition does not support revocation"),
+        )?,
+    };
+
+    let (credential, rev_reg, rev_reg_delta) = match revocation_config {
+        Some(revocation) => {
+            let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) =
+                revocation.reg_def;
+            let mut rev_reg = match revocation.registry {
+                RevocationRegistry::RevocationRegistryV1(v1) => v1.value.clone(),
+            };
+            let (credential, delta) = sign_credential(
+                &cred_pub_key,
+                cred_def_private,
+                cred_offer,
+                cred_request,
+                cred_values,
+                Some(RevocationSigning {
+                    reg_def: rev_reg_def,
+                    reg_def_private: revocation.reg_def_private,
+                    registry: &mut rev_reg,
+                    registry_idx: revocation.registry_idx,
+                }),
+            )?;
+            (credential, Some(rev_reg), delta)
+        }
+        None => {
+            let (credential, _) = sign_credential(
+                &cred_pub_key,
+                cred_def_private,
+                cred_offer,
+                cred_request,
+                cred_values,
+                None,
+            )?;
+            (credential, None, None)
+        }
+    };
+
+    let rev_reg = rev_reg
+        .map(|reg| RevocationRegistry::RevocationRegistryV1(RevocationRegistryV1 { value: reg }));
+    let rev_reg_delta = rev_reg_delta.map(|delta| {
+        RevocationRegistryDelta::RevocationRegistryDeltaV1(RevocationRegistryDeltaV1 {
+            value: delta,
+        })
+    });
+
+    trace!(
+        "create_credential <<< credential {:?}, rev_reg_delta {:?}",
+        secret!(&credential),
+        rev_reg_delta
+    );
+
+    Ok((credential, rev_reg, rev_reg_delta))
+}
+
+/// Issue a batch of credentials against a single revocation registry.
+///
+/// The credentials are signed in order, and the final registry is returned
+/// along with a single delta covering all of the issued indices. The delta is
+/// only produced for registries using `ISSUANCE_ON_DEMAND`, as with
+/// `create_credential`. The indices in `registry_used` have already been
+/// assigned to credentials and are rejected, as are duplicate indices within
+/// the batch. No state is returned unless every credential is issued.
+pub fn create_credential_batch(
+    cred_def: &CredentialDefinition,
+    cred_def_private: &CredentialDefinitionPrivate,
+    rev_reg_def: &RevocationRegistryDefinition,
+    rev_reg_def_private: &RevocationRegistryDefinitionPrivate,
+    rev_reg: &RevocationRegistry,
+    registry_used: &HashSet<u32>,
+    entries: Vec<CredentialBatchEntry>,
+) -> Result<(
+    Vec<Credential>,
+    RevocationRegistry,
+    Option<RevocationRegistryDelta>,
+)> {
+    trace!(
+        "create_credential_batch >>> cred_def: {:?}, cred_def_private: {:?}, rev_reg_def: {:?}, \
+            rev_reg_def_private: {:?}, rev_reg: {:?}, registry_used: {:?}, entries: {:?}",
+        cred_def,
+        secret!(&cred_def_private),
+        rev_reg_def,
+        secret!(&rev_reg_def_private),
+        rev_reg,
+        registry_used,
+        entries.len()
+    );
+
+    let cred_pub_key = match cred_def {
         CredentialDefinition::CredentialDefinitionV1(cd) => cd.get_public_key().map_err(
             err_map!(Input, "Credential definition does not support revocation"),
         )?,
     };
+    let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
+    let max_cred_num = rev_reg_def.value.max_cred_num;
+
+    let mut issued = HashSet::with_capacity(entries.len());
+    for entry in entries.iter() {
+        check_credential_request(entry.cred_offer, entry.cred_request)?;
+        check_credential_encodings(&entry.cred_values, &HashMap::new())?;
+        if entry.registry_idx == 0 || entry.registry_idx > max_cred_num {
+            return Err(err_msg!(
+                InvalidUserRevocId,
+                "Revocation index {} is outside of the range 1..={}",
+                entry.registry_idx,
+                max_cred_num
+            ));
+        }
+        if registry_used.contains(&entry.registry_idx) {
+            return Err(err_msg!(
+                InvalidUserRevocId,
+                "Revocation index {} has already been used",
+                entry.registry_idx
+            ));
+        }
+        if !issued.insert(entry.registry_idx) {
+            return Err(err_msg!(
+                InvalidUserRevocId,
+                "Duplicate revocation index in batch: {}",
+                entry.registry_idx
+            ));
+        }
+    }
+
+    let prev_rev_reg = match rev_reg {
+        RevocationRegistry::RevocationRegistryV1(v1) => &v1.value,
+    };
+    let mut next_rev_reg = prev_rev_reg.clone();
+    let mut credentials = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let (credential, _delta) = sign_credential(
+            &cred_pub_key,
+            cred_def_private,
+            entry.cred_offer,
+            entry.cred_request,
+            entry.cred_values,
+            Some(RevocationSigning {
+                reg_def: rev_reg_def,
+                reg_def_private: rev_reg_def_private,
+                registry: &mut next_rev_reg,
+                registry_idx: entry.registry_idx,
+            }),
+        )?;
+        credentials.push(credential);
+    }
+
+    let rev_reg_delta = if rev_reg_def.value.issuance_type.to_bool() || credentials.is_empty() {
+        None
+    } else {
+        Some(RevocationRegistryDelta::RevocationRegistryDeltaV1(
+            RevocationRegistryDeltaV1 {
+                value: CryptoRevocationRegistryDelta::from_parts(
+                    Some(prev_rev_reg),
+                    &next_rev_reg,
+                    &issued,
+                    &HashSet::new(),
+                ),
+            },
+        ))
+    };
+    let rev_reg = RevocationRegistry::RevocationRegistryV1(RevocationRegistryV1 {
+        value: next_rev_reg,
+    });
+
+    trace!(
+        "create_credential_batch <<< credentials: {:?}, rev_reg: {:?}, rev_reg_delta: {:?}",
+        credentials.len(),
+        rev_reg,
+        rev_reg_delta
+    );
+
+    Ok((credentials, rev_reg, rev_reg_delta))
+}
+
+struct RevocationSigning<'a> {
+    reg_def: &'a RevocationRegistryDefinitionV1,
+    reg_def_private: &'a RevocationRegistryDefinitionPrivate,
+    registry: &'a mut CryptoRevocationRegistry,
+    registry_idx: u32,
+}
+
+fn check_credential_request(
+    cred_offer: &CredentialOffer,
+    cred_request: &CredentialRequest,
+) -> Result<()> {
+    if cred_request.cred_def_id != cred_offer.cred_def_id {
+        return Err(err_msg!(
+            "Credential request is for credential definition {} but the offer is for {}",
+            cred_request.cred_def_id,
+            cred_offer.cred_def_id
+        ));
+    }
+    Ok(())
+}
+
+fn sign_credential(
+    cred_pub_key: &CredentialPublicKey,
+    cred_def_private: &CredentialDefinitionPrivate,
+    cred_offer: &CredentialOffer,
+    cred_request: &CredentialRequest,
+    cred_values: CredentialValues,
+    revocation: Option<RevocationSigning>,
+) -> Result<(Credential, Option<CryptoRevocationRegistryDelta>)> {
     let credential_values = build_credential_values(&cred_values.0, None)?;
 
     let (
@@ -332,16 +602,9 @@ pub fn create_credential(
         rev_reg,
         rev_reg_delta,
         witness,
-    ) = match revocation_config {
+    ) = match revocation {
         Some(revocation) => {
-            let (rev_reg_def, reg_reg_id) = match revocation.reg_def {
-                RevocationRegistryDefinition::RevocationRegistryDefinitionV1(v1) => {
-                    (&v1.value, v1.id.clone())
-                }
-            };
-            let mut rev_reg = match revocation.registry {
-                RevocationRegistry::RevocationRegistryV1(v1) => v1.value.clone(),
-            };
+            let rev_reg_def = &revocation.reg_def.value;
             let (credential_signature, signature_correctness_proof, witness, delta) =
                 ClIssuer::sign_credential_with_revoc(
                     &cred_request.prover_did.0,
@@ -350,15 +613,16 @@ pub fn create_credential(
                     cred_offer.nonce.as_native(),
                     cred_request.nonce.as_native(),
                     &credential_values,
-                    &cred_rev_pub_key,
+                    cred_pub_key,
                     &cred_def_private.value,
                     revocation.registry_idx,
                     rev_reg_def.max_cred_num,
                     rev_reg_def.issuance_type.to_bool(),
-                    &mut rev_reg,
+                    revocation.registry,
                     &revocation.reg_def_private.value,
                 )?;
 
+            let reg_reg_id = revocation.reg_def.id.clone();
             let cred_rev_reg_id = match cred_offer.method_name.as_ref() {
                 Some(_method_name) => Some(reg_reg_id.to_unqualified()),
                 _ => Some(reg_reg_id),
@@ -367,7 +631,7 @@ pub fn create_credential(
                 credential_signature,
                 signature_correctness_proof,
                 cred_rev_reg_id,
-                Some(rev_reg),
+                Some(revocation.registry.clone()),
                 delta,
                 Some(witness),
             )
@@ -380,7 +644,7 @@ pub fn create_credential(
                 cred_offer.nonce.as_native(),
                 cred_request.nonce.as_native(),
                 &credential_values,
-                &cred_rev_pub_key,
+                cred_pub_key,
                 &cred_def_private.value,
             )?;
             (signature, correctness_proof, None, None, None, None)
@@ -394,25 +658,11 @@ pub fn create_credential(
         values: cred_values,
         signature: credential_signature,
         signature_correctness_proof,
-        rev_reg: rev_reg.clone(),
+        rev_reg,
         witness,
     };
 
-    let rev_reg = rev_reg
-        .map(|reg| RevocationRegistry::RevocationRegistryV1(RevocationRegistryV1 { value: reg }));
-    let rev_reg_delta = rev_reg_delta.map(|delta| {
-        RevocationRegistryDelta::RevocationRegistryDeltaV1(RevocationRegistryDeltaV1 {
-            value: delta,
-        })
-    });
-
-    trace!(
-        "create_credential <<< credential {:?}, rev_reg_delta {:?}",
-        secret!(&credential),
-        rev_reg_delta
-    );
-
-    Ok((credential, rev_reg, rev_reg_delta))
+    Ok((credential, rev_reg_delta))
 }
 
 pub fn revoke_credential(
@@ -461,19 +711,30 @@ pub fn revoke_credential(
     Ok((new_rev_reg, delta))
 }
 
-#[allow(dead_code)]
+/// Restore a revoked credential to the registry.
+///
+/// `issued` and `revoked` must contain the indices of the registry which have
+/// been issued and remain active, and which have been issued and later
+/// revoked, as reported by `RevocationRegistryTracker`. They are used to ensure
+/// that only a revoked credential index is restored. Under
+/// `ISSUANCE_ON_DEMAND`, restoring an index which was never issued is rejected
+/// rather than issuing it.
 pub fn unrevoke_credential(
     cred_def: &CredentialDefinition,
     rev_reg_def: &RevocationRegistryDefinition,
     rev_reg_priv: &RevocationRegistryDefinitionPrivate,
     rev_reg: &RevocationRegistry,
     cred_rev_idx: u32,
+    issued: &BTreeSet<u32>,
+    revoked: &BTreeSet<u32>,
 ) -> Result<(RevocationRegistry, RevocationRegistryDelta)> {
     trace!(
-        "recover >>> rev_reg_def: {:?}, rev_reg: {:?}, cred_rev_idx: {:?}",
+        "unrevoke >>> rev_reg_def: {:?}, rev_reg: {:?}, cred_rev_idx: {:?}, issued: {:?}, revoked: {:?}",
         rev_reg_def,
         rev_reg,
-        secret!(&cred_rev_idx)
+        secret!(&cred_rev_idx),
+        secret!(issued),
+        secret!(revoked)
     );
 
     let cred_pub_key = match cred_def {
@@ -484,9 +745,38 @@ pub fn unrevoke_credential(
             ))?
         }
     };
-    let max_cred_num = match rev_reg_def {
-        RevocationRegistryDefinition::RevocationRegistryDefinitionV1(v1) => v1.value.max_cred_num,
-    };
+    let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
+    let max_cred_num = rev_reg_def.value.max_cred_num;
+    if cred_rev_idx == 0 || cred_rev_idx > max_cred_num {
+        return Err(err_msg!(
+            InvalidUserRevocId,
+            "Revocation index {} is outside of the range 1..={}",
+            cred_rev_idx,
+            max_cred_num
+        ));
+    }
+    if let Some(idx) = issued.intersection(revoked).next() {
+        return Err(err_msg!(
+            "Credential index {} is listed as both issued and revoked",
+            idx
+        ));
+    }
+    if !revoked.contains(&cred_rev_idx) {
+        let issued_by_default = rev_reg_def.value.issuance_type.to_bool();
+        return Err(if issued.contains(&cred_rev_idx) || issued_by_default {
+            err_msg!(
+                InvalidUserRevocId,
+                "Credential index {} is not revoked",
+                cred_rev_idx
+            )
+        } else {
+            err_msg!(
+                InvalidUserRevocId,
+                "Credential index {} has not been issued",
+                cred_rev_idx
+            )
+        });
+    }
     let mut rev_reg = match rev_reg {
         RevocationRegistry::RevocationRegistryV1(v1) => v1.value.clone(),
     };
@@ -503,7 +793,7 @@ pub fn unrevoke_credential(
     let delta = RevocationRegistryDelta::RevocationRegistryDeltaV1(RevocationRegistryDeltaV1 {
         value: rev_reg_delta,
     });
-    trace!("recover <<< rev_reg_delta {:?}", delta);
+    trace!("unrevoke <<< rev_reg_delta {:?}", delta);
 
     Ok((new_rev_reg, delta))
 }
@@ -523,3 +813,193 @@ pub fn merge_revocation_registry_deltas(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::services::prover;
+    use crate::services::test_support::{create_registry, TestRegistry};
+
+    struct Fixture {
+        schema: Schema,
+        cred_def: CredentialDefinition,
+        cred_def_private: CredentialDefinitionPrivate,
+        cred_offer: CredentialOffer,
+        cred_request: CredentialRequest,
+    }
+
+    impl Fixture {
+        fn new(attr_names: &[&str]) -> Self {
+            let origin_did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+            let schema = create_schema(
+                &origin_did,
+                "test",
+                "1.0",
+                AttributeNames(attr_names.iter().map(|name| name.to_string()).collect()),
+                Some(15),
+            )
+            .unwrap();
+            let (cred_def, cred_def_private, cred_key_proof) = create_credential_definition(
+                &origin_did,
+                &schema,
+                "tag",
+                SignatureType::CL,
+                CredentialDefinitionConfig::new(false),
+            )
+            .unwrap();
+            let cred_offer =
+                create_credential_offer(schema.id(), &cred_def, &cred_key_proof).unwrap();
+            let link_secret = prover::create_link_secret().unwrap();
+            let (cred_request, _) = prover::create_credential_request(
+                &origin_did,
+                &cred_def,
+                &link_secret,
+                "default",
+                &cred_offer,
+            )
+            .unwrap();
+            Self {
+                schema,
+                cred_def,
+                cred_def_private,
+                cred_offer,
+                cred_request,
+            }
+        }
+    }
+
+    fn issue(
+        attr_names: &[&str],
+        cred_values: MakeCredentialValues,
+    ) -> Result<(
+        Credential,
+        Option<RevocationRegistry>,
+        Option<RevocationRegistryDelta>,
+    )> {
+        let fixture = Fixture::new(attr_names);
+        create_credential_for_schema(
+            &fixture.schema,
+            &fixture.cred_def,
+            &fixture.cred_def_private,
+            &fixture.cred_offer,
+            &fixture.cred_request,
+            cred_values,
+            None,
+        )
+    }
+
+    #[test]
+    fn schema_attributes_are_normalized() {
+        let origin_did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+        let schema = create_schema(
+            &origin_did,
+            "test",
+            "1.0",
+            vec!["First Name".to_string(), "age".to_string()].into(),
+            Some(15),
+        )
+        .unwrap();
+        let expected: HashSet<String> = [
+            "firstname".to_string(),
+            "age".to_string(),
+            LINK_SECRET_ATTRIBUTE.to_string(),
+        ]
+        .into();
+        assert_eq!(schema_attributes(&schema), expected);
+    }
+
+    #[test]
+    fn create_credential_requires_default_encoding() {
+        let fixture = Fixture::new(&["balance"]);
+        let create = |cred_values: MakeCredentialValues| {
+            create_credential(
+                &fixture.cred_def,
+                &fixture.cred_def_private,
+                &fixture.cred_offer,
+                &fixture.cred_request,
+                cred_values.into(),
+                None,
+            )
+        };
+
+        let mut cred_values = MakeCredentialValues::default();
+        cred_values.add_raw("balance", "12").unwrap();
+        create(cred_values).unwrap();
+
+        let mut cred_values = MakeCredentialValues::default();
+        cred_values
+            .add_typed("balance", "12", AttributeEncoding::Decimal { scale: 2 })
+            .unwrap();
+        let err = create(cred_values).unwrap_err();
+        assert!(err.to_string().contains("\"balance\""), "{}", err);
+    }
+
+    #[test]
+    fn create_credential_typed_encodings() {
+        let mut cred_values = MakeCredentialValues::default();
+        cred_values
+            .add_typed("balance", "12", AttributeEncoding::Decimal { scale: 2 })
+            .unwrap();
+        cred_values
+            .add_typed(
+                "level",
+                "5",
+                AttributeEncoding::Integer {
+                    min: -100,
+                    max: 100,
+                },
+            )
+            .unwrap();
+        cred_values.add_raw("name", "Alex").unwrap();
+        let (credential, _, _) = issue(&["balance", "level", "name"], cred_values).unwrap();
+        assert_eq!(credential.values.0["balance"].encoded, "1200");
+        assert_eq!(credential.values.0["level"].encoded, "105");
+    }
+
+    #[test]
+    fn create_credential_rejects_mismatched_encoding() {
+        for (raw, encoded) in [("Alex", "1"), ("5", "105"), ("28", "29")] {
+            let mut cred_values = MakeCredentialValues::default();
+            cred_values.add_encoded("name", raw, encoded.to_string());
+            assert_kind!(Input, issue(&["name"], cred_values));
+        }
+
+        // the encoded value is checked against the encoding it was created with
+        let mut cred_values = MakeCredentialValues::default();
+        cred_values
+            .add_typed("balance", "12", AttributeEncoding::Decimal { scale: 2 })
+            .unwrap();
+        cred_values.values.0.get_mut("balance").unwrap().encoded = "1201".to_string();
+        let err = issue(&["balance"], cred_values).unwrap_err();
+        assert!(err.to_string().contains("\"balance\""), "{}", err);
+    }
+
+    #[test]
+    fn unrevoke_rejects_unissued_index() {
+        let TestRegistry {
+            cred_def,
+            rev_reg_def,
+            rev_reg_def_private,
+            registry,
+            ..
+        } = create_registry(IssuanceType::ISSUANCE_ON_DEMAND);
+        let unrevoke = |idx, issued: &[u32], revoked: &[u32]| {
+            unrevoke_credential(
+                &cred_def,
+                &rev_reg_def,
+                &rev_reg_def_private,
+                &registry,
+                idx,
+                &issued.iter().copied().collect(),
+                &revoked.iter().copied().collect(),
+            )
+        };
+
+        let err = unrevoke(3, &[1], &[2]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidUserRevocId);
+        assert!(err.to_string().contains("has not been issued"), "{}", err);
+        assert_kind!(InvalidUserRevocId, unrevoke(1, &[1], &[2]));
+        assert_kind!(Input, unrevoke(2, &[2], &[2]));
+    }
+}
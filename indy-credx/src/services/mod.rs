@@ -1,11 +1,22 @@
+mod credential_selection;
 mod helpers;
+mod pres_request_builder;
+mod revocation_tracker;
+mod verification;
 
 pub mod issuer;
+pub mod nonce_registry;
 pub mod prover;
+pub mod registry_history;
+pub mod registry_set;
+pub mod resolver;
+pub mod status_list;
 pub mod tails;
+#[cfg(test)]
+pub(crate) mod test_support;
 pub mod types;
 pub mod verifier;
 
 pub mod utils {
-    pub use super::helpers::encode_credential_attribute;
+    pub use super::helpers::{encode_credential_attribute, AttributeEncoding};
 }
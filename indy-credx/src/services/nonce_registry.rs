@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use indy_data_types::anoncreds::nonce::Nonce;
+use indy_data_types::utils::base58;
+use sha2::{Digest, Sha256};
+
+use super::helpers::lock;
+use super::resolver::LedgerResolver;
+use super::types::*;
+use super::verifier;
+use crate::error::Result;
+
+/// The state of a nonce issued in a presentation request.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonceRecord {
+    /// The digest of the presentation request, as produced by `request_digest`.
+    pub request_digest: String,
+    /// The time (in seconds since the Unix epoch) at which the nonce expires.
+    pub expires_at: u64,
+    pub consumed: bool,
+}
+
+/// Storage for the nonces issued by a `NonceRegistry`.
+///
+/// Consumed nonces should be retained until they expire, so that a replayed
+/// presentation can be reported as reusing its nonce.
+pub trait NonceStorage {
+    /// Record a newly issued nonce, failing if the nonce is already recorded.
+    fn insert(&self, nonce: &str, record: NonceRecord) -> Result<()>;
+
+    /// Atomically mark a nonce as consumed, provided it was recorded for the
+    /// request with the digest `request_digest`, has not been consumed and
+    /// has not expired at `now`. Otherwise the record is left unchanged.
+    fn consume(&self, nonce: &str, request_digest: &str, now: u64) -> Result<ConsumeOutcome>;
+
+    /// Remove the records of nonces which expired at or before `now`, returning
+    /// the number of records removed.
+    fn purge_expired(&self, now: u64) -> Result<usize>;
+}
+
+/// The outcome of `NonceStorage::consume`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsumeOutcome {
+    Consumed,
+    Unknown,
+    AlreadyConsumed,
+    Expired,
+    RequestMismatch,
+}
+
+impl ConsumeOutcome {
+    /// Check the outcome of consuming a nonce against a record.
+    pub fn check(record: &NonceRecord, request_digest: &str, now: u64) -> Self {
+        if record.consumed {
+            Self::AlreadyConsumed
+        } else if record.expires_at <= now {
+            Self::Expired
+        } else if record.request_digest != request_digest {
+            Self::RequestMismatch
+        } else {
+            Self::Consumed
+        }
+    }
+}
+
+/// Nonce storage held in memory. Expired nonces are kept until
+/// `purge_expired` is called.
+#[derive(Debug, Default)]
+pub struct InMemoryNonceStorage {
+    records: Mutex<HashMap<String, NonceRecord>>,
+}
+
+impl InMemoryNonceStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        lock(&self.records).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        lock(&self.records).is_empty()
+    }
+}
+
+impl NonceStorage for InMemoryNonceStorage {
+    fn insert(&self, nonce: &str, record: NonceRecord) -> Result<()> {
+        let mut records = lock(&self.records);
+        if records.contains_key(nonce) {
+            return Err(err_msg!(InvalidNonce, "Nonce is already recorded"));
+        }
+        records.insert(nonce.to_string(), record);
+        Ok(())
+    }
+
+    fn consume(&self, nonce: &str, request_digest: &str, now: u64) -> Result<ConsumeOutcome> {
+        let mut records = lock(&self.records);
+        let Some(record) = records.get_mut(nonce) else {
+            return Ok(ConsumeOutcome::Unknown);
+        };
+        let outcome = ConsumeOutcome::check(record, request_digest, now);
+        if outcome == ConsumeOutcome::Consumed {
+            record.consumed = true;
+        }
+        Ok(outcome)
+    }
+
+    fn purge_expired(&self, now: u64) -> Result<usize> {
+        let mut records = lock(&self.records);
+        let count = records.len();
+        records.retain(|_, record| record.expires_at > now);
+        Ok(count - records.len())
+    }
+}
+
+/// Tracks the nonces of issued presentation requests, so that each nonce is
+/// accepted for a single presentation before it expires.
+#[derive(Debug)]
+pub struct NonceRegistry<S> {
+    storage: S,
+    ttl: Duration,
+}
+
+impl<S: NonceStorage> NonceRegistry<S> {
+    /// Create a registry in which nonces expire `ttl` after being issued.
+    pub fn new(storage: S, ttl: Duration) -> Self {
+        Self { storage, ttl }
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Generate a nonce, create the presentation request for it and record
+    /// the nonce with the digest of the request.
+    pub fn issue_request<F>(&self, create_request: F) -> Result<PresentationRequest>
+    where
+        F: FnOnce(Nonce) -> Result<PresentationRequest>,
+    {
+        let request = create_request(verifier::generate_nonce()?)?;
+        self.record_request(&request)?;
+        Ok(request)
+    }
+
+    /// Record the nonce of a presentation request created by the caller.
+    pub fn record_request(&self, pres_req: &PresentationRequest) -> Result<()> {
+        let record = NonceRecord {
+            request_digest: request_digest(pres_req)?,
+            expires_at: unix_time().saturating_add(self.ttl.as_secs()),
+            consumed: false,
+        };
+        self.storage.insert(&pres_req.value().nonce, record)
+    }
+
+    /// Consume the nonce of a presentation request. The request must match
+    /// the recorded request, and the nonce must not be expired or already
+    /// consumed. A request which is rejected does not consume the nonce.
+    pub fn consume_request(&self, pres_req: &PresentationRequest) -> Result<()> {
+        let outcome = self.storage.consume(
+            &pres_req.value().nonce,
+            &request_digest(pres_req)?,
+            unix_time(),
+        )?;
+        match outcome {
+            ConsumeOutcome::Consumed => Ok(()),
+            ConsumeOutcome::Unknown => Err(err_msg!(InvalidNonce, "Unknown nonce")),
+            ConsumeOutcome::AlreadyConsumed => {
+                Err(err_msg!(InvalidNonce, "Nonce has already been used"))
+            }
+            ConsumeOutcome::Expired => Err(err_msg!(InvalidNonce, "Nonce has expired")),
+            ConsumeOutcome::RequestMismatch => Err(err_msg!(
+                InvalidNonce,
+                "Presentation request does not match the request issued for the nonce"
+            )),
+        }
+    }
+
+    /// Consume the nonce of the presentation request and verify the
+    /// presentation. The nonce is consumed even if verification fails.
+    pub fn verify_presentation(
+        &self,
+        presentation: &Presentation,
+        pres_req: &PresentationRequest,
+        resolver: &impl LedgerResolver,
+        policy: &VerificationPolicy,
+    ) -> Result<VerificationReport> {
+        self.consume_request(pres_req)?;
+        verifier::verify_presentation_report_with_resolver(presentation, pres_req, resolver, policy)
+    }
+
+    /// Remove the records of expired nonces from storage.
+    pub fn purge_expired(&self) -> Result<usize> {
+        self.storage.purge_expired(unix_time())
+    }
+}
+
+/// The digest of a presentation request, which identifies the request
+/// recorded for a nonce.
+pub fn request_digest(pres_req: &PresentationRequest) -> Result<String> {
+    // serializing through a `Value` sorts the object keys
+    let json = serde_json::to_value(pres_req)?.to_string();
+    Ok(base58::encode(Sha256::digest(json.as_bytes())))
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorKind;
+
+    fn create_request(nonce: Nonce) -> Result<PresentationRequest> {
+        Ok(serde_json::from_value(serde_json::json!({
+            "nonce": nonce,
+            "name": "pres_req_1",
+            "version": "0.1",
+            "requested_attributes": {
+                "attr1_referent": {"name": "name"}
+            },
+            "requested_predicates": {}
+        }))?)
+    }
+
+    #[test]
+    fn nonce_registry_consumes_once() {
+        let registry = NonceRegistry::new(InMemoryNonceStorage::new(), Duration::from_secs(60));
+        let request = registry.issue_request(create_request).unwrap();
+        assert_eq!(registry.storage().len(), 1);
+        assert_kind!(InvalidNonce, registry.record_request(&request));
+
+        registry.consume_request(&request).unwrap();
+        let err = registry.consume_request(&request).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidNonce);
+        assert_eq!(err.message.as_deref(), Some("Nonce has already been used"));
+
+        let unknown = create_request(verifier::generate_nonce().unwrap()).unwrap();
+        assert_kind!(InvalidNonce, registry.consume_request(&unknown));
+    }
+
+    #[test]
+    fn nonce_registry_rejects_expired_and_altered_requests() {
+        let registry = NonceRegistry::new(InMemoryNonceStorage::new(), Duration::ZERO);
+        let request = registry.issue_request(create_request).unwrap();
+        let err = registry.consume_request(&request).unwrap_err();
+        assert_eq!(err.message.as_deref(), Some("Nonce has expired"));
+        assert_eq!(registry.purge_expired().unwrap(), 1);
+        assert!(registry.storage().is_empty());
+
+        let registry = NonceRegistry::new(InMemoryNonceStorage::new(), Duration::from_secs(60));
+        let request = registry.issue_request(create_request).unwrap();
+        let mut altered = serde_json::to_value(&request).unwrap();
+        altered["requested_attributes"]["attr1_referent"]["name"] = serde_json::json!("age");
+        let altered: PresentationRequest = serde_json::from_value(altered).unwrap();
+        let err = registry.consume_request(&altered).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidNonce);
+        assert!(err.message.unwrap().contains("does not match"));
+
+        // the altered request did not consume the nonce
+        registry.consume_request(&request).unwrap();
+    }
+}
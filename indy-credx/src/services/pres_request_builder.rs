@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+
+use super::helpers::new_nonce;
+use super::types::*;
+use crate::error::Error;
+use indy_data_types::qualifiable::is_fully_qualified;
+
+/// A restriction on the credentials which may satisfy a requested attribute or
+/// predicate, converted to a WQL query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Restriction(Query);
+
+impl Restriction {
+    pub fn schema_id(schema_id: &SchemaId) -> Self {
+        Self::eq("schema_id", &schema_id.0)
+    }
+
+    pub fn schema_issuer_did(did: &DidValue) -> Self {
+        Self::eq("schema_issuer_did", &did.0)
+    }
+
+    pub fn schema_name(name: &str) -> Self {
+        Self::eq("schema_name", name)
+    }
+
+    pub fn schema_version(version: &str) -> Self {
+        Self::eq("schema_version", version)
+    }
+
+    pub fn cred_def_id(cred_def_id: &CredentialDefinitionId) -> Self {
+        Self::eq("cred_def_id", &cred_def_id.0)
+    }
+
+    pub fn issuer_did(did: &DidValue) -> Self {
+        Self::eq("issuer_did", &did.0)
+    }
+
+    /// The credential has the raw `value` for an attribute, which must be one
+    /// of the attribute names requested by the referent.
+    pub fn attr_value(name: &str, value: &str) -> Self {
+        Self::eq(&format!("attr::{}::value", name), value)
+    }
+
+    /// The credential contains an attribute.
+    pub fn attr_marker(name: &str) -> Self {
+        Self::eq(&format!("attr::{}::marker", name), "1")
+    }
+
+    /// All of the restrictions apply.
+    pub fn all(restrictions: impl IntoIterator<Item = Restriction>) -> Self {
+        Self(Query::And(restrictions.into_iter().map(|r| r.0).collect()))
+    }
+
+    /// At least one of the restrictions applies.
+    pub fn any(restrictions: impl IntoIterator<Item = Restriction>) -> Self {
+        Self(Query::Or(restrictions.into_iter().map(|r| r.0).collect()))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Self(Query::Not(Box::new(self.0)))
+    }
+
+    fn eq(tag: &str, value: &str) -> Self {
+        Self(Query::Eq(tag.to_string(), value.to_string()))
+    }
+}
+
+impl From<Restriction> for Query {
+    fn from(restriction: Restriction) -> Query {
+        restriction.0
+    }
+}
+
+/// Add a condition to the restrictions of a requested attribute or predicate.
+fn add_restriction(restrictions: &mut Option<Query>, restriction: Restriction) {
+    *restrictions = Some(match restrictions.take() {
+        None => restriction.0,
+        Some(Query::And(mut queries)) => {
+            queries.push(restriction.0);
+            Query::And(queries)
+        }
+        Some(query) => Query::And(vec![query, restriction.0]),
+    });
+}
+
+/// An attribute or attribute group requested by a `PresentationRequestBuilder`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestedAttribute(AttributeInfo);
+
+impl RequestedAttribute {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(AttributeInfo {
+            name: Some(name.into()),
+            names: None,
+            restrictions: None,
+            non_revoked: None,
+        })
+    }
+
+    /// Request a group of attributes which must be revealed from the same credential.
+    pub fn group<N>(names: impl IntoIterator<Item = N>) -> Self
+    where
+        N: Into<String>,
+    {
+        Self(AttributeInfo {
+            name: None,
+            names: Some(names.into_iter().map(Into::into).collect()),
+            restrictions: None,
+            non_revoked: None,
+        })
+    }
+
+    /// Add a restriction. Multiple restrictions must all be satisfied.
+    pub fn restriction(mut self, restriction: Restriction) -> Self {
+        add_restriction(&mut self.0.restrictions, restriction);
+        self
+    }
+
+    pub fn non_revoked(mut self, from: Option<u64>, to: Option<u64>) -> Self {
+        self.0.non_revoked = Some(NonRevocedInterval { from, to });
+        self
+    }
+}
+
+/// A predicate requested by a `PresentationRequestBuilder`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestedPredicate(PredicateInfo);
+
+impl RequestedPredicate {
+    pub fn new(name: impl Into<String>, p_type: PredicateTypes, p_value: i32) -> Self {
+        Self(PredicateInfo {
+            name: name.into(),
+            p_type,
+            p_value,
+            restrictions: None,
+            non_revoked: None,
+        })
+    }
+
+    /// Add a restriction. Multiple restrictions must all be satisfied.
+    pub fn restriction(mut self, restriction: Restriction) -> Self {
+        add_restriction(&mut self.0.restrictions, restriction);
+        self
+    }
+
+    pub fn non_revoked(mut self, from: Option<u64>, to: Option<u64>) -> Self {
+        self.0.non_revoked = Some(NonRevocedInterval { from, to });
+        self
+    }
+}
+
+/// Build a `PresentationRequest`.
+///
+/// Attributes and predicates added without a referent are assigned the
+/// referents `attr{n}_referent` and `predicate{n}_referent`, numbered from 1 in
+/// the order they are added. A nonce is generated unless one is provided. The
+/// request uses the second version of the format when any restriction refers
+/// to a fully-qualified identifier, and otherwise the first.
+#[derive(Debug)]
+pub struct PresentationRequestBuilder {
+    name: String,
+    version: String,
+    nonce: Option<Nonce>,
+    non_revoked: Option<NonRevocedInterval>,
+    attributes: Vec<(Option<String>, AttributeInfo)>,
+    predicates: Vec<(Option<String>, PredicateInfo)>,
+}
+
+impl PresentationRequestBuilder {
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            nonce: None,
+            non_revoked: None,
+            attributes: Vec::new(),
+            predicates: Vec::new(),
+        }
+    }
+
+    pub fn nonce(mut self, nonce: Nonce) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Set the non-revocation interval for all attributes and predicates
+    /// which do not define their own.
+    pub fn non_revoked(mut self, from: Option<u64>, to: Option<u64>) -> Self {
+        self.non_revoked = Some(NonRevocedInterval { from, to });
+        self
+    }
+
+    pub fn attribute(mut self, attribute: RequestedAttribute) -> Self {
+        self.attributes.push((None, attribute.0));
+        self
+    }
+
+    pub fn attribute_with_referent(
+        mut self,
+        referent: impl Into<String>,
+        attribute: RequestedAttribute,
+    ) -> Self {
+        self.attributes.push((Some(referent.into()), attribute.0));
+        self
+    }
+
+    pub fn predicate(mut self, predicate: RequestedPredicate) -> Self {
+        self.predicates.push((None, predicate.0));
+        self
+    }
+
+    pub fn predicate_with_referent(
+        mut self,
+        referent: impl Into<String>,
+        predicate: RequestedPredicate,
+    ) -> Self {
+        self.predicates.push((Some(referent.into()), predicate.0));
+        self
+    }
+
+    pub fn build(self) -> Result<PresentationRequest, Error> {
+        let requested_attributes = assign_referents(self.attributes, "attr")?;
+        let requested_predicates = assign_referents(self.predicates, "predicate")?;
+        let qualified = requested_attributes
+            .values()
+            .filter_map(|info| info.restrictions.as_ref())
+            .chain(
+                requested_predicates
+                    .values()
+                    .filter_map(|info| info.restrictions.as_ref()),
+            )
+            .any(uses_qualified_identifiers);
+        let payload = PresentationRequestPayload {
+            nonce: match self.nonce {
+                Some(nonce) => nonce,
+                None => new_nonce()?,
+            },
+            name: self.name,
+            version: self.version,
+            requested_attributes,
+            requested_predicates,
+            non_revoked: self.non_revoked,
+        };
+        let request = if qualified {
+            PresentationRequest::PresentationRequestV2(payload)
+        } else {
+            PresentationRequest::PresentationRequestV1(payload)
+        };
+        request.validate()?;
+        Ok(request)
+    }
+}
+
+fn assign_referents<T>(
+    items: Vec<(Option<String>, T)>,
+    prefix: &str,
+) -> Result<HashMap<String, T>, Error> {
+    let mut result = HashMap::with_capacity(items.len());
+    for (idx, (referent, info)) in items.into_iter().enumerate() {
+        let referent = referent.unwrap_or_else(|| format!("{}{}_referent", prefix, idx + 1));
+        if result.contains_key(&referent) {
+            return Err(err_msg!("Duplicate referent: {}", referent));
+        }
+        result.insert(referent, info);
+    }
+    Ok(result)
+}
+
+fn uses_qualified_identifiers(query: &Query) -> bool {
+    let qualified = |tag: &String, value: &String| {
+        Credential::QUALIFIABLE_TAGS.contains(&tag.as_str()) && is_fully_qualified(value)
+    };
+    match query {
+        Query::Eq(tag, value)
+        | Query::Neq(tag, value)
+        | Query::Gt(tag, value)
+        | Query::Gte(tag, value)
+        | Query::Lt(tag, value)
+        | Query::Lte(tag, value)
+        | Query::Like(tag, value) => qualified(tag, value),
+        Query::In(tag, values) => values.iter().any(|value| qualified(tag, value)),
+        Query::Exist(_) => false,
+        Query::And(queries) | Query::Or(queries) => queries.iter().any(uses_qualified_identifiers),
+        Query::Not(query) => uses_qualified_identifiers(query),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presentation_request_builder_assigns_referents() {
+        let cred_def_id = CredentialDefinitionId(
+            "NcYxiDXkpYi6ov5FcYDi1e:3:CL:NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0:tag".to_string(),
+        );
+        let request = PresentationRequestBuilder::new("pres_req_1", "0.1")
+            .nonce(Nonce::from_dec("123456").unwrap())
+            .non_revoked(None, Some(100))
+            .attribute(
+                RequestedAttribute::new("name")
+                    .restriction(Restriction::cred_def_id(&cred_def_id))
+                    .restriction(Restriction::attr_value("name", "Alex")),
+            )
+            .attribute(RequestedAttribute::group(["sex", "height"]))
+            .attribute_with_referent("self", RequestedAttribute::new("phone"))
+            .predicate(RequestedPredicate::new("age", PredicateTypes::GE, 18))
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            request,
+            PresentationRequest::PresentationRequestV1(_)
+        ));
+        let payload = request.value();
+        assert_eq!(payload.nonce.as_ref(), "123456");
+        assert_eq!(
+            payload.non_revoked,
+            Some(NonRevocedInterval {
+                from: None,
+                to: Some(100)
+            })
+        );
+        let mut referents: Vec<_> = payload.requested_attributes.keys().collect();
+        referents.sort();
+        assert_eq!(referents, ["attr1_referent", "attr2_referent", "self"]);
+        assert_eq!(
+            payload.requested_attributes["attr1_referent"].restrictions,
+            Some(Query::And(vec![
+                Query::Eq("cred_def_id".to_string(), cred_def_id.0.clone()),
+                Query::Eq("attr::name::value".to_string(), "Alex".to_string()),
+            ]))
+        );
+        assert!(payload
+            .requested_predicates
+            .contains_key("predicate1_referent"));
+    }
+
+    #[test]
+    fn presentation_request_builder_selects_version() {
+        let qualified = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", Some("sov"));
+        let request = PresentationRequestBuilder::new("pres_req_1", "0.1")
+            .predicate(
+                RequestedPredicate::new("age", PredicateTypes::GE, 18).restriction(
+                    Restriction::any([
+                        Restriction::schema_name("gvt"),
+                        Restriction::issuer_did(&qualified),
+                    ]),
+                ),
+            )
+            .build()
+            .unwrap();
+        assert!(matches!(
+            request,
+            PresentationRequest::PresentationRequestV2(_)
+        ));
+    }
+
+    #[test]
+    fn presentation_request_builder_rejects_invalid_requests() {
+        assert_kind!(
+            Input,
+            PresentationRequestBuilder::new("pres_req_1", "0.1")
+                .attribute(RequestedAttribute::new("name"))
+                .attribute_with_referent("attr1_referent", RequestedAttribute::new("age"))
+                .build()
+        );
+        assert_kind!(
+            Input,
+            PresentationRequestBuilder::new("pres_req_1", "0.1")
+                .attribute(RequestedAttribute::group(Vec::<String>::new()))
+                .build()
+        );
+    }
+}
@@ -2,24 +2,27 @@ use std::collections::{HashMap, HashSet};
 
 use super::types::*;
 use crate::anoncreds_clsignatures::{
-    CredentialPublicKey, Issuer as ClIssuer, Prover as ClProver,
-    RevocationRegistry as CryptoRevocationRegistry, SubProofRequest, Verifier as ClVerifier,
-    Witness,
+    CredentialPublicKey, Error as ClError, Issuer as ClIssuer, Prover as ClProver,
+    RevocationRegistry as CryptoRevocationRegistry, RevocationTailsAccessor, SubProofRequest, Tail,
+    Verifier as ClVerifier, Witness,
 };
 use crate::error::Result;
 use crate::services::helpers::*;
+use crate::services::resolver::{LedgerResolver, MapResolver};
+use crate::services::verifier::{process_operator, Filter};
 use indy_data_types::anoncreds::{
     credential::AttributeValues,
-    pres_request::{PresentationRequestPayload, RequestedAttributeInfo, RequestedPredicateInfo},
+    pres_request::{
+        PredicateTypes, PresentationRequestPayload, RequestedAttributeInfo, RequestedPredicateInfo,
+    },
     presentation::{
         AttributeValue, Identifier, RequestedProof, RevealedAttributeGroupInfo,
         RevealedAttributeInfo, SubProofReferent,
     },
+    wql::Query,
 };
 use indy_data_types::{Qualifiable, Validatable};
 
-use super::tails::TailsFileReader;
-
 pub fn create_link_secret() -> Result<LinkSecret> {
     LinkSecret::new().map_err(err_map!(Unexpected))
 }
@@ -130,8 +133,26 @@ pub fn create_presentation(
     schemas: &HashMap<SchemaId, &Schema>,
     cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
 ) -> Result<Presentation> {
-    trace!("create_proof >>> credentials: {:?}, pres_req: {:?}, credentials: {:?}, self_attested: {:?}, link_secret: {:?}, schemas: {:?}, cred_defs: {:?}",
-            credentials, pres_req, credentials, &self_attested, secret!(&link_secret), schemas, cred_defs);
+    let resolver = MapResolver {
+        schemas,
+        cred_defs,
+        rev_reg_defs: None,
+        rev_regs: None,
+    };
+    create_presentation_with_resolver(pres_req, credentials, self_attested, link_secret, &resolver)
+}
+
+/// Create a presentation, looking up the schemas and credential definitions
+/// of the presented credentials as they are needed.
+pub fn create_presentation_with_resolver(
+    pres_req: &PresentationRequest,
+    credentials: PresentCredentials,
+    self_attested: Option<HashMap<String, String>>,
+    link_secret: &LinkSecret,
+    resolver: &impl LedgerResolver,
+) -> Result<Presentation> {
+    trace!("create_proof >>> credentials: {:?}, pres_req: {:?}, credentials: {:?}, self_attested: {:?}, link_secret: {:?}",
+            credentials, pres_req, credentials, &self_attested, secret!(&link_secret));
 
     if credentials.is_empty()
         && self_attested
@@ -165,17 +186,20 @@ pub fn create_presentation(
         }
         let credential = present.cred;
 
-        let Schema::SchemaV1(schema) = *schemas
-            .get(&credential.schema_id)
+        let schema = resolver
+            .resolve_schema(&credential.schema_id)?
             .ok_or_else(|| err_msg!("Schema not provided for ID: {}", credential.schema_id))?;
+        let Schema::SchemaV1(schema) = &*schema;
 
-        let cred_def = *cred_defs.get(&credential.cred_def_id).ok_or_else(|| {
-            err_msg!(
-                "Credential Definition not provided for ID: {}",
-                credential.cred_def_id
-            )
-        })?;
-        let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
+        let cred_def = resolver
+            .resolve_cred_def(&credential.cred_def_id)?
+            .ok_or_else(|| {
+                err_msg!(
+                    "Credential Definition not provided for ID: {}",
+                    credential.cred_def_id
+                )
+            })?;
+        let CredentialDefinition::CredentialDefinitionV1(cred_def) = &*cred_def;
 
         let credential_pub_key = CredentialPublicKey::build_from_parts(
             &cred_def.value.primary,
@@ -245,18 +269,20 @@ pub fn create_presentation(
     Ok(full_proof)
 }
 
-pub fn create_or_update_revocation_state(
-    tails_reader: TailsFileReader,
+pub fn create_or_update_revocation_state<TR>(
+    tails_reader: &TR,
     revoc_reg_def: &RevocationRegistryDefinition,
     rev_reg_delta: &RevocationRegistryDelta,
     rev_reg_idx: u32,
     timestamp: u64,
     rev_state: Option<&CredentialRevocationState>,
-) -> Result<CredentialRevocationState> {
+) -> Result<CredentialRevocationState>
+where
+    TR: RevocationTailsAccessor + ?Sized,
+{
     trace!(
-        "create_or_update_revocation_state >>> , tails_reader: {:?}, revoc_reg_def: {:?}, \
+        "create_or_update_revocation_state >>> revoc_reg_def: {:?}, \
 rev_reg_delta: {:?}, rev_reg_idx: {}, timestamp: {:?}, rev_state: {:?}",
-        tails_reader,
         revoc_reg_def,
         rev_reg_delta,
         rev_reg_idx,
@@ -266,6 +292,7 @@ rev_reg_delta: {:?}, rev_reg_idx: {}, timestamp: {:?}, rev_state: {:?}",
 
     let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(revoc_reg_def) = revoc_reg_def;
     let RevocationRegistryDelta::RevocationRegistryDeltaV1(rev_reg_delta) = rev_reg_delta;
+    let tails_reader = TailsAccessorRef(tails_reader);
 
     let witness = match rev_state {
         None => Witness::new(
@@ -294,6 +321,126 @@ rev_reg_delta: {:?}, rev_reg_idx: {}, timestamp: {:?}, rev_state: {:?}",
     })
 }
 
+/// Adapts an unsized tails accessor for the witness calculations.
+struct TailsAccessorRef<'a, TR: ?Sized>(&'a TR);
+
+impl<TR> RevocationTailsAccessor for TailsAccessorRef<'_, TR>
+where
+    TR: RevocationTailsAccessor + ?Sized,
+{
+    fn access_tail(
+        &self,
+        tail_id: u32,
+        accessor: &mut dyn FnMut(&Tail),
+    ) -> std::result::Result<(), ClError> {
+        self.0.access_tail(tail_id, accessor)
+    }
+}
+
+/// Find the credentials which can satisfy each referent of a presentation request.
+///
+/// A credential matches a requested attribute when it contains all of the
+/// requested names and satisfies the restrictions, using the same evaluation
+/// as the verifier with the attribute values revealed. A credential matches a
+/// requested predicate when its encoded value for the attribute satisfies the
+/// predicate and the restrictions are met. An error is returned when the
+/// schema or credential definition identifier of a credential is malformed.
+pub fn match_credentials(
+    pres_req: &PresentationRequest,
+    credentials: &[&Credential],
+) -> Result<CredentialMatches> {
+    trace!(
+        "match_credentials >>> pres_req: {:?}, credentials: {:?}",
+        pres_req,
+        secret!(credentials)
+    );
+
+    let pres_req = pres_req.value();
+    let filters = credentials
+        .iter()
+        .enumerate()
+        .map(|(idx, cred)| {
+            Filter::new(&cred.schema_id, &cred.cred_def_id)
+                .map_err(err_map!("Invalid identifiers for credential {}", idx))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut matches = CredentialMatches::default();
+
+    for (referent, info) in pres_req.requested_attributes.iter() {
+        let names = if let Some(name) = &info.name {
+            vec![name.clone()]
+        } else if let Some(names) = &info.names {
+            names.to_owned()
+        } else {
+            return Err(err_msg!(
+                r#"Requested attribute {} should contain "name" or "names" param"#,
+                referent
+            ));
+        };
+
+        let mut candidates = Vec::new();
+        'cred: for (idx, cred) in credentials.iter().enumerate() {
+            let mut attr_value_map = HashMap::with_capacity(names.len());
+            for name in names.iter() {
+                match get_credential_values_for_attribute(&cred.values.0, name) {
+                    Some(values) => {
+                        attr_value_map.insert(name.clone(), Some(values.raw));
+                    }
+                    None => continue 'cred,
+                }
+            }
+            let attr_value_map = attr_value_map
+                .iter()
+                .map(|(name, raw)| (name.clone(), raw.as_deref()))
+                .collect();
+            if check_restrictions(info.restrictions.as_ref(), &attr_value_map, &filters[idx]) {
+                candidates.push(idx);
+            }
+        }
+        matches.attributes.insert(referent.clone(), candidates);
+    }
+
+    for (referent, info) in pres_req.requested_predicates.iter() {
+        let mut candidates = Vec::new();
+        for (idx, cred) in credentials.iter().enumerate() {
+            let value = match get_credential_values_for_attribute(&cred.values.0, &info.name)
+                .and_then(|values| values.encoded.parse::<i32>().ok())
+            {
+                Some(value) => value,
+                None => continue,
+            };
+            let satisfied = match info.p_type {
+                PredicateTypes::GE => value >= info.p_value,
+                PredicateTypes::GT => value > info.p_value,
+                PredicateTypes::LE => value <= info.p_value,
+                PredicateTypes::LT => value < info.p_value,
+            };
+            let mut attr_value_map = HashMap::new();
+            attr_value_map.insert(info.name.clone(), None);
+            if satisfied
+                && check_restrictions(info.restrictions.as_ref(), &attr_value_map, &filters[idx])
+            {
+                candidates.push(idx);
+            }
+        }
+        matches.predicates.insert(referent.clone(), candidates);
+    }
+
+    trace!("match_credentials <<< matches: {:?}", matches);
+
+    Ok(matches)
+}
+
+fn check_restrictions(
+    restrictions: Option<&Query>,
+    attr_value_map: &HashMap<String, Option<&str>>,
+    filter: &Filter,
+) -> bool {
+    restrictions.map_or(true, |query| {
+        process_operator(attr_value_map, query, filter).is_ok()
+    })
+}
+
 fn prepare_credential_for_proving(
     requested_attributes: HashSet<(String, bool)>,
     requested_predicates: HashSet<String>,
@@ -0,0 +1,237 @@
+use std::collections::{BTreeSet, HashSet};
+
+use super::helpers::revocation_registry_delta_parts;
+use super::issuer::merge_revocation_registry_deltas;
+use super::types::*;
+use crate::anoncreds_clsignatures::{
+    RevocationRegistry as CryptoRevocationRegistry,
+    RevocationRegistryDelta as CryptoRevocationRegistryDelta,
+};
+use crate::error::Result;
+use indy_data_types::anoncreds::rev_reg::{RevocationRegistryDeltaV1, RevocationRegistryV1};
+
+/// A revocation registry delta published at a timestamp.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevocationRegistryHistoryEntry {
+    pub timestamp: u64,
+    pub delta: RevocationRegistryDelta,
+}
+
+/// The chain of deltas published for a revocation registry, from which the
+/// registry state at any timestamp can be reconstructed.
+///
+/// Deltas must be added in the order they were published, each one applying
+/// to the accumulator produced by the previous delta. When the history does
+/// not begin with the initial delta of the registry, cumulative deltas only
+/// describe the changes since the first recorded entry.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevocationRegistryHistory {
+    rev_reg_id: RevocationRegistryId,
+    entries: Vec<RevocationRegistryHistoryEntry>,
+}
+
+impl RevocationRegistryHistory {
+    pub fn new(rev_reg_id: RevocationRegistryId) -> Self {
+        Self {
+            rev_reg_id,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn rev_reg_id(&self) -> &RevocationRegistryId {
+        &self.rev_reg_id
+    }
+
+    pub fn entries(&self) -> &[RevocationRegistryHistoryEntry] {
+        &self.entries
+    }
+
+    /// Add a delta published at `timestamp`, which must be later than the
+    /// latest entry and apply to the accumulator it produced.
+    pub fn add_delta(&mut self, timestamp: u64, delta: RevocationRegistryDelta) -> Result<()> {
+        if let Some(latest) = self.entries.last() {
+            check_continuity(latest, timestamp, &delta)?;
+        }
+        self.entries
+            .push(RevocationRegistryHistoryEntry { timestamp, delta });
+        Ok(())
+    }
+
+    /// The latest entry published at or before `timestamp`.
+    pub fn entry_at(&self, timestamp: u64) -> Option<&RevocationRegistryHistoryEntry> {
+        let count = self
+            .entries
+            .partition_point(|entry| entry.timestamp <= timestamp);
+        count.checked_sub(1).map(|idx| &self.entries[idx])
+    }
+
+    /// The registry state in effect at `timestamp`.
+    pub fn registry_at(&self, timestamp: u64) -> Option<RevocationRegistry> {
+        self.entry_at(timestamp)
+            .map(|entry| registry_from_delta(&entry.delta))
+    }
+
+    /// The credential indices issued and revoked in the registry state in
+    /// effect at `timestamp`, as recorded by the deltas since the first entry.
+    pub fn indices_at(&self, timestamp: u64) -> Result<Option<(BTreeSet<u32>, BTreeSet<u32>)>> {
+        Ok(match self.delta_between(None, timestamp)? {
+            Some(delta) => {
+                let parts = revocation_registry_delta_parts(&delta)?;
+                Some((parts.issued, parts.revoked))
+            }
+            None => None,
+        })
+    }
+
+    /// The cumulative delta from the registry state in effect at `from` to the
+    /// state in effect at `to`, or from the first entry when `from` is `None`.
+    ///
+    /// Returns `None` when no delta was published at or before `to`. When the
+    /// state did not change between the two timestamps, the delta is empty.
+    pub fn delta_between(
+        &self,
+        from: Option<u64>,
+        to: u64,
+    ) -> Result<Option<RevocationRegistryDelta>> {
+        if from.is_some_and(|from| from > to) {
+            return Err(err_msg!(
+                "Invalid interval: {} is later than {}",
+                from.unwrap_or_default(),
+                to
+            ));
+        }
+        let Some(latest) = self.entry_at(to) else {
+            return Ok(None);
+        };
+        let mut deltas = self
+            .entries
+            .iter()
+            .filter(|entry| from.map_or(true, |from| entry.timestamp > from))
+            .take_while(|entry| entry.timestamp <= to);
+        let Some(first) = deltas.next() else {
+            let RevocationRegistry::RevocationRegistryV1(registry) =
+                registry_from_delta(&latest.delta);
+            let empty = HashSet::new();
+            return Ok(Some(RevocationRegistryDelta::RevocationRegistryDeltaV1(
+                RevocationRegistryDeltaV1 {
+                    value: CryptoRevocationRegistryDelta::from_parts(
+                        Some(&registry.value),
+                        &registry.value,
+                        &empty,
+                        &empty,
+                    ),
+                },
+            )));
+        };
+        let mut result = first.delta.clone();
+        for entry in deltas {
+            result = merge_revocation_registry_deltas(&result, &entry.delta)?;
+        }
+        Ok(Some(result))
+    }
+}
+
+impl Validatable for RevocationRegistryHistory {
+    fn validate(&self) -> std::result::Result<(), ValidationError> {
+        for pair in self.entries.windows(2) {
+            check_continuity(&pair[0], pair[1].timestamp, &pair[1].delta)
+                .map_err(|err| invalid!("Invalid revocation registry history: {}", err))?;
+        }
+        Ok(())
+    }
+}
+
+fn check_continuity(
+    latest: &RevocationRegistryHistoryEntry,
+    timestamp: u64,
+    delta: &RevocationRegistryDelta,
+) -> Result<()> {
+    if timestamp <= latest.timestamp {
+        return Err(err_msg!(
+            "Delta timestamp {} is not later than the latest entry at {}",
+            timestamp,
+            latest.timestamp
+        ));
+    }
+    let prev_accum = revocation_registry_delta_parts(delta)?.prev_accum;
+    if prev_accum != Some(revocation_registry_delta_parts(&latest.delta)?.accum) {
+        return Err(err_msg!(
+            "Delta at {} does not apply to the registry state at {}",
+            timestamp,
+            latest.timestamp
+        ));
+    }
+    Ok(())
+}
+
+fn registry_from_delta(delta: &RevocationRegistryDelta) -> RevocationRegistry {
+    let RevocationRegistryDelta::RevocationRegistryDeltaV1(delta) = delta;
+    RevocationRegistry::RevocationRegistryV1(RevocationRegistryV1 {
+        value: CryptoRevocationRegistry::from(delta.value.clone()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::issuer;
+    use crate::services::test_support::{accum, create_registry, parts, TestRegistry};
+
+    #[test]
+    fn registry_history_reconstructs_states() {
+        let TestRegistry {
+            cred_def,
+            rev_reg_def,
+            rev_reg_def_private,
+            registry: initial_reg,
+            initial_delta,
+            ..
+        } = create_registry(IssuanceType::ISSUANCE_BY_DEFAULT);
+        let (reg_1, delta_1) = issuer::revoke_credential(
+            &cred_def,
+            &rev_reg_def,
+            &rev_reg_def_private,
+            &initial_reg,
+            1,
+        )
+        .unwrap();
+        let (reg_2, delta_2) =
+            issuer::revoke_credential(&cred_def, &rev_reg_def, &rev_reg_def_private, &reg_1, 2)
+                .unwrap();
+
+        let mut history = RevocationRegistryHistory::new(rev_reg_def.id().clone());
+        history.add_delta(10, initial_delta).unwrap();
+        history.add_delta(20, delta_1.clone()).unwrap();
+        assert_kind!(Input, history.add_delta(20, delta_2.clone()));
+        assert_kind!(Input, history.add_delta(30, delta_1));
+        history.add_delta(30, delta_2).unwrap();
+
+        assert!(history.registry_at(5).is_none());
+        assert_eq!(
+            accum(&history.registry_at(10).unwrap()),
+            accum(&initial_reg)
+        );
+        assert_eq!(accum(&history.registry_at(25).unwrap()), accum(&reg_1));
+        assert_eq!(accum(&history.registry_at(100).unwrap()), accum(&reg_2));
+
+        let delta = parts(&history.delta_between(Some(10), 30).unwrap().unwrap());
+        assert_eq!(delta.prev_accum, Some(accum(&initial_reg)));
+        assert_eq!(delta.accum, accum(&reg_2));
+        assert_eq!(delta.revoked, BTreeSet::from([1, 2]));
+
+        let unchanged = parts(&history.delta_between(Some(20), 25).unwrap().unwrap());
+        assert_eq!(unchanged.prev_accum, Some(unchanged.accum));
+        assert!(unchanged.revoked.is_empty());
+        assert!(history.delta_between(None, 5).unwrap().is_none());
+        assert_kind!(Input, history.delta_between(Some(30), 20));
+
+        let (_, revoked) = history.indices_at(25).unwrap().unwrap();
+        assert_eq!(revoked, BTreeSet::from([1]));
+
+        let restored: RevocationRegistryHistory =
+            serde_json::from_value(serde_json::to_value(&history).unwrap()).unwrap();
+        restored.validate().unwrap();
+        assert_eq!(restored.entries().len(), 3);
+        assert_eq!(accum(&restored.registry_at(25).unwrap()), accum(&reg_1));
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::HashSet;
+
+use super::issuer;
+use super::tails::TailsWriter;
+use super::types::*;
+use crate::error::Result;
+use indy_data_types::Qualifiable;
+
+/// The revocation registry tag used for the registry at position `seq` (starting
+/// from 1) within a set created with the base tag `tag`.
+pub fn registry_tag(tag: &str, seq: usize) -> String {
+    format!("{}-{}", tag, seq)
+}
+
+/// A revocation registry owned by a `RevocationRegistrySet`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevocationRegistrySetEntry {
+    pub definition: RevocationRegistryDefinition,
+    pub definition_private: RevocationRegistryDefinitionPrivate,
+    pub registry: RevocationRegistry,
+    pub tracker: RevocationRegistryTracker,
+}
+
+impl RevocationRegistrySetEntry {
+    pub fn id(&self) -> &RevocationRegistryId {
+        self.definition.id()
+    }
+}
+
+/// A newly created revocation registry which must be published.
+#[derive(Debug)]
+pub struct NewRevocationRegistry {
+    pub definition: RevocationRegistryDefinition,
+    pub initial_delta: RevocationRegistryDelta,
+}
+
+/// The result of issuing a credential through a `RevocationRegistrySet`.
+#[derive(Debug)]
+pub struct IssuedCredential {
+    pub credential: Credential,
+    pub rev_reg_id: RevocationRegistryId,
+    pub registry_idx: u32,
+    pub rev_reg_delta: Option<RevocationRegistryDelta>,
+    /// The registry created to hold this credential, if the previous one was full.
+    pub new_registry: Option<NewRevocationRegistry>,
+}
+
+/// The set of revocation registries created for a single credential definition.
+///
+/// New credentials are assigned to the most recently created (active) registry.
+/// When it reaches its maximum credential count, the next registry is created
+/// using the tag `registry_tag(tag, n)` and the provided tails writer.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevocationRegistrySet {
+    origin_did: DidValue,
+    cred_def_id: CredentialDefinitionId,
+    tag: String,
+    rev_reg_type: RegistryType,
+    issuance_type: IssuanceType,
+    max_cred_num: u32,
+    registries: Vec<RevocationRegistrySetEntry>,
+}
+
+impl RevocationRegistrySet {
+    pub fn new(
+        origin_did: &DidValue,
+        cred_def: &CredentialDefinition,
+        tag: &str,
+        rev_reg_type: RegistryType,
+        issuance_type: IssuanceType,
+        max_cred_num: u32,
+    ) -> Result<Self> {
+        if max_cred_num == 0 {
+            return Err(err_msg!(
+                "Maximum credential count must be greater than zero"
+            ));
+        }
+        // check that registry identifiers can be derived for this definition
+        issuer::make_revocation_registry_id(origin_did, cred_def, tag, rev_reg_type)?;
+        Ok(Self {
+            origin_did: origin_did.clone(),
+            cred_def_id: cred_def.id().clone(),
+            tag: tag.to_string(),
+            rev_reg_type,
+            issuance_type,
+            max_cred_num,
+            registries: Vec::new(),
+        })
+    }
+
+    pub fn cred_def_id(&self) -> &CredentialDefinitionId {
+        &self.cred_def_id
+    }
+
+    pub fn registries(&self) -> &[RevocationRegistrySetEntry] {
+        &self.registries
+    }
+
+    /// The registry which will receive the next issued credential, if any.
+    pub fn active(&self) -> Option<&RevocationRegistrySetEntry> {
+        self.registries.last()
+    }
+
+    pub fn get(&self, rev_reg_id: &RevocationRegistryId) -> Option<&RevocationRegistrySetEntry> {
+        self.registries
+            .iter()
+            .find(|entry| entry.id() == rev_reg_id)
+    }
+
+    pub fn get_mut(
+        &mut self,
+        rev_reg_id: &RevocationRegistryId,
+    ) -> Option<&mut RevocationRegistrySetEntry> {
+        self.registries
+            .iter_mut()
+            .find(|entry| entry.id() == rev_reg_id)
+    }
+
+    /// Find the registry and index assigned to a credential issued from this set.
+    ///
+    /// The registry identifier of the credential may be qualified or unqualified.
+    pub fn locate_credential(
+        &self,
+        credential: &Credential,
+    ) -> Option<(&RevocationRegistryId, u32)> {
+        let rev_reg_id = credential.rev_reg_id.as_ref()?.to_unqualified();
+        let idx = credential.signature.extract_index()?;
+        let entry = self
+            .registries
+            .iter()
+            .find(|entry| entry.id().to_unqualified() == rev_reg_id)?;
+        if idx == 0 || idx > entry.tracker.max_cred_num() {
+            return None;
+        }
+        Some((entry.id(), idx))
+    }
+
+    /// Create the next registry in the set and make it the active registry.
+    pub fn create_registry<TW>(
+        &mut self,
+        cred_def: &CredentialDefinition,
+        tails_writer: &mut TW,
+    ) -> Result<NewRevocationRegistry>
+    where
+        TW: TailsWriter,
+    {
+        self.check_cred_def(cred_def)?;
+        let tag = registry_tag(&self.tag, self.registries.len() + 1);
+        let (definition, definition_private, registry, initial_delta) =
+            issuer::create_revocation_registry(
+                &self.origin_did,
+                cred_def,
+                &tag,
+                self.rev_reg_type,
+                self.issuance_type,
+                self.max_cred_num,
+                tails_writer,
+            )?;
+        let tracker = RevocationRegistryTracker::from_definition(&definition);
+        self.registries.push(RevocationRegistrySetEntry {
+            definition: definition.clone(),
+            definition_private,
+            registry,
+            tracker,
+        });
+        Ok(NewRevocationRegistry {
+            definition,
+            initial_delta,
+        })
+    }
+
+    /// Issue a credential in the active registry, first creating a new registry
+    /// when there is no active registry or it is full.
+    ///
+    /// A registry created by this call is removed from the set again when
+    /// signing fails, although its tails file has already been written.
+    pub fn create_credential<TW>(
+        &mut self,
+        cred_def: &CredentialDefinition,
+        cred_def_private: &CredentialDefinitionPrivate,
+        cred_offer: &CredentialOffer,
+        cred_request: &CredentialRequest,
+        cred_values: CredentialValues,
+        tails_writer: &mut TW,
+    ) -> Result<IssuedCredential>
+    where
+        TW: TailsWriter,
+    {
+        self.check_cred_def(cred_def)?;
+        let new_registry = match self.active() {
+            Some(entry) if !entry.tracker.is_full() => None,
+            _ => Some(self.create_registry(cred_def, tails_writer)?),
+        };
+        let entry = self
+            .registries
+            .last_mut()
+            .ok_or_else(|| err_msg!(Unexpected, "No active revocation registry"))?;
+
+        let mut tracker = entry.tracker.clone();
+        let registry_idx = tracker.allocate()?;
+        let signed = issuer::create_credential(
+            cred_def,
+            cred_def_private,
+            cred_offer,
+            cred_request,
+            cred_values,
+            Some(CredentialRevocationConfig {
+                reg_def: &entry.definition,
+                reg_def_private: &entry.definition_private,
+                registry: &entry.registry,
+                registry_idx,
+                registry_used: &HashSet::new(),
+            }),
+        );
+        let (credential, registry, rev_reg_delta) = match signed {
+            Ok(signed) => signed,
+            Err(err) => {
+                if new_registry.is_some() {
+                    self.registries.pop();
+                }
+                return Err(err);
+            }
+        };
+        let entry = self
+            .registries
+            .last_mut()
+            .ok_or_else(|| err_msg!(Unexpected, "No active revocation registry"))?;
+        if let Some(registry) = registry {
+            entry.registry = registry;
+        }
+        entry.tracker = tracker;
+
+        Ok(IssuedCredential {
+            credential,
+            rev_reg_id: entry.id().clone(),
+            registry_idx,
+            rev_reg_delta,
+            new_registry,
+        })
+    }
+
+    fn check_cred_def(&self, cred_def: &CredentialDefinition) -> Result<()> {
+        if cred_def.id() != &self.cred_def_id {
+            return Err(err_msg!(
+                Input,
+                "Credential definition {} does not match the registry set",
+                cred_def.id()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::prover;
+    use crate::services::tails::TailsBufferWriter;
+
+    struct Fixture {
+        origin_did: DidValue,
+        schema: Schema,
+        cred_def: CredentialDefinition,
+        cred_def_private: CredentialDefinitionPrivate,
+        cred_key_proof: CredentialKeyCorrectnessProof,
+        link_secret: LinkSecret,
+        set: RevocationRegistrySet,
+    }
+
+    impl Fixture {
+        fn new(max_cred_num: u32) -> Self {
+            let origin_did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+            let schema = issuer::create_schema(
+                &origin_did,
+                "test",
+                "1.0",
+                vec!["name".to_string()].into(),
+                Some(15),
+            )
+            .unwrap();
+            let (cred_def, cred_def_private, cred_key_proof) =
+                issuer::create_credential_definition(
+                    &origin_did,
+                    &schema,
+                    "tag",
+                    SignatureType::CL,
+                    CredentialDefinitionConfig::new(true),
+                )
+                .unwrap();
+            let set = RevocationRegistrySet::new(
+                &origin_did,
+                &cred_def,
+                "rev",
+                RegistryType::CL_ACCUM,
+                IssuanceType::ISSUANCE_BY_DEFAULT,
+                max_cred_num,
+            )
+            .unwrap();
+            Self {
+                origin_did,
+                schema,
+                cred_def,
+                cred_def_private,
+                cred_key_proof,
+                link_secret: prover::create_link_secret().unwrap(),
+                set,
+            }
+        }
+
+        fn issue(
+            &mut self,
+            values: MakeCredentialValues,
+            tails_writer: &mut TailsBufferWriter,
+        ) -> Result<IssuedCredential> {
+            let cred_offer = issuer::create_credential_offer(
+                self.schema.id(),
+                &self.cred_def,
+                &self.cred_key_proof,
+            )
+            .unwrap();
+            let (cred_request, _) = prover::create_credential_request(
+                &self.origin_did,
+                &self.cred_def,
+                &self.link_secret,
+                "default",
+                &cred_offer,
+            )
+            .unwrap();
+            self.set.create_credential(
+                &self.cred_def,
+                &self.cred_def_private,
+                &cred_offer,
+                &cred_request,
+                values.into(),
+                tails_writer,
+            )
+        }
+    }
+
+    fn name_values() -> MakeCredentialValues {
+        let mut values = MakeCredentialValues::default();
+        values.add_raw("name", "Alex").unwrap();
+        values
+    }
+
+    #[test]
+    fn registry_set_rolls_over() {
+        let mut fixture = Fixture::new(2);
+        assert!(fixture.set.active().is_none());
+
+        let mut tails_writer = TailsBufferWriter::new(None);
+        let issued: Vec<_> = (0..3)
+            .map(|_| fixture.issue(name_values(), &mut tails_writer).unwrap())
+            .collect();
+        let set = &fixture.set;
+
+        assert_eq!(set.registries().len(), 2);
+        assert!(issued[0].new_registry.is_some());
+        assert!(issued[1].new_registry.is_none());
+        let new_registry = issued[2].new_registry.as_ref().unwrap();
+        assert_eq!(new_registry.definition.id(), set.active().unwrap().id());
+        assert!(new_registry
+            .definition
+            .id()
+            .0
+            .ends_with(&registry_tag("rev", 2)));
+
+        let located: Vec<_> = issued
+            .iter()
+            .map(|issued| {
+                let (rev_reg_id, idx) = set.locate_credential(&issued.credential).unwrap();
+                assert_eq!(rev_reg_id, &issued.rev_reg_id);
+                idx
+            })
+            .collect();
+        assert_eq!(located, [1, 2, 1]);
+        assert_ne!(issued[0].rev_reg_id, issued[2].rev_reg_id);
+
+        let mut credential = issued[2].credential.try_clone().unwrap();
+        credential.rev_reg_id = Some(issued[2].rev_reg_id.to_qualified("sov").unwrap());
+        assert_ne!(credential.rev_reg_id.as_ref(), Some(&issued[2].rev_reg_id));
+        assert_eq!(
+            set.locate_credential(&credential),
+            Some((&issued[2].rev_reg_id, 1))
+        );
+    }
+
+    #[test]
+    fn registry_set_removes_registry_when_signing_fails() {
+        let mut fixture = Fixture::new(1);
+        let mut tails_writer = TailsBufferWriter::new(None);
+
+        let mut values = MakeCredentialValues::default();
+        values.add_raw("other", "Alex").unwrap();
+        assert!(fixture.issue(values, &mut tails_writer).is_err());
+        assert!(fixture.set.active().is_none());
+
+        let issued = fixture.issue(name_values(), &mut tails_writer).unwrap();
+        assert!(issued.new_registry.is_some());
+        let mut values = MakeCredentialValues::default();
+        values.add_raw("other", "Alex").unwrap();
+        assert!(fixture.issue(values, &mut tails_writer).is_err());
+        assert_eq!(fixture.set.registries().len(), 1);
+        assert_eq!(fixture.set.active().unwrap().id(), &issued.rev_reg_id);
+        assert!(fixture.set.active().unwrap().tracker.is_full());
+    }
+}
@@ -0,0 +1,603 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use super::helpers::lock;
+use super::types::*;
+use crate::error::Result;
+use indy_data_types::Qualifiable;
+
+/// A ledger object returned by a `LedgerResolver`, either borrowed from the
+/// resolver or shared with it.
+#[derive(Debug)]
+pub enum Resolved<'a, T> {
+    Borrowed(&'a T),
+    Shared(Arc<T>),
+}
+
+impl<T> Deref for Resolved<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            Self::Borrowed(obj) => obj,
+            Self::Shared(obj) => obj,
+        }
+    }
+}
+
+impl<T> Clone for Resolved<'_, T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Borrowed(obj) => Self::Borrowed(obj),
+            Self::Shared(obj) => Self::Shared(obj.clone()),
+        }
+    }
+}
+
+/// The state of a revocation registry in effect at a timestamp, along with
+/// the period during which it was in effect.
+#[derive(Clone, Debug)]
+pub struct RegistryState<'a> {
+    pub rev_reg: Resolved<'a, RevocationRegistry>,
+    /// The timestamp of the entry which published the state.
+    pub published: u64,
+    /// The timestamp of the next entry, which replaced the state, if any.
+    pub replaced: Option<u64>,
+}
+
+/// Select the latest entry of a registry history at or before `timestamp`.
+pub(crate) fn registry_state_at<'r>(
+    entries: impl IntoIterator<Item = (u64, &'r RevocationRegistry)>,
+    timestamp: u64,
+) -> Option<RegistryState<'r>> {
+    let mut state: Option<(u64, &RevocationRegistry)> = None;
+    let mut replaced: Option<u64> = None;
+    for (entry_time, rev_reg) in entries {
+        if entry_time <= timestamp {
+            if state.map_or(true, |(published, _)| entry_time > published) {
+                state = Some((entry_time, rev_reg));
+            }
+        } else if replaced.map_or(true, |replaced| entry_time < replaced) {
+            replaced = Some(entry_time);
+        }
+    }
+    state.map(|(published, rev_reg)| RegistryState {
+        rev_reg: Resolved::Borrowed(rev_reg),
+        published,
+        replaced,
+    })
+}
+
+/// Look up the ledger objects referenced by credentials and presentations as
+/// they are needed.
+///
+/// Each method returns `Ok(None)` when the object is not known, and an error
+/// only when the lookup itself fails. Presentations created for V1 requests
+/// always reference unqualified identifiers, so a resolver holding objects
+/// with qualified identifiers must also match their unqualified forms, as
+/// `InMemoryResolver::with_unqualified_matching` does.
+pub trait LedgerResolver {
+    fn resolve_schema(&self, id: &SchemaId) -> Result<Option<Resolved<'_, Schema>>>;
+
+    fn resolve_cred_def(
+        &self,
+        id: &CredentialDefinitionId,
+    ) -> Result<Option<Resolved<'_, CredentialDefinition>>>;
+
+    fn resolve_rev_reg_def(
+        &self,
+        _id: &RevocationRegistryId,
+    ) -> Result<Option<Resolved<'_, RevocationRegistryDefinition>>> {
+        Ok(None)
+    }
+
+    /// Resolve the state of a revocation registry published at `timestamp`.
+    fn resolve_rev_reg(
+        &self,
+        _id: &RevocationRegistryId,
+        _timestamp: u64,
+    ) -> Result<Option<Resolved<'_, RevocationRegistry>>> {
+        Ok(None)
+    }
+
+    /// Resolve the state of a revocation registry in effect at `timestamp`,
+    /// which is the latest entry published at or before it.
+    ///
+    /// Resolvers which keep a history of registry entries should override
+    /// this method. By default only an entry published exactly at `timestamp`
+    /// is found, and it is not known when the state was replaced.
+    fn resolve_rev_reg_at(
+        &self,
+        id: &RevocationRegistryId,
+        timestamp: u64,
+    ) -> Result<Option<RegistryState<'_>>> {
+        Ok(self
+            .resolve_rev_reg(id, timestamp)?
+            .map(|rev_reg| RegistryState {
+                rev_reg,
+                published: timestamp,
+                replaced: None,
+            }))
+    }
+}
+
+impl<R: LedgerResolver + ?Sized> LedgerResolver for &R {
+    fn resolve_schema(&self, id: &SchemaId) -> Result<Option<Resolved<'_, Schema>>> {
+        (**self).resolve_schema(id)
+    }
+
+    fn resolve_cred_def(
+        &self,
+        id: &CredentialDefinitionId,
+    ) -> Result<Option<Resolved<'_, CredentialDefinition>>> {
+        (**self).resolve_cred_def(id)
+    }
+
+    fn resolve_rev_reg_def(
+        &self,
+        id: &RevocationRegistryId,
+    ) -> Result<Option<Resolved<'_, RevocationRegistryDefinition>>> {
+        (**self).resolve_rev_reg_def(id)
+    }
+
+    fn resolve_rev_reg(
+        &self,
+        id: &RevocationRegistryId,
+        timestamp: u64,
+    ) -> Result<Option<Resolved<'_, RevocationRegistry>>> {
+        (**self).resolve_rev_reg(id, timestamp)
+    }
+
+    fn resolve_rev_reg_at(
+        &self,
+        id: &RevocationRegistryId,
+        timestamp: u64,
+    ) -> Result<Option<RegistryState<'_>>> {
+        (**self).resolve_rev_reg_at(id, timestamp)
+    }
+}
+
+/// A resolver over ledger objects which are held in memory.
+///
+/// Objects are found by their exact identifiers unless the resolver is
+/// created with `with_unqualified_matching`.
+#[derive(Debug, Default)]
+pub struct InMemoryResolver {
+    schemas: HashMap<SchemaId, Schema>,
+    cred_defs: HashMap<CredentialDefinitionId, CredentialDefinition>,
+    rev_reg_defs: HashMap<RevocationRegistryId, RevocationRegistryDefinition>,
+    rev_regs: HashMap<RevocationRegistryId, BTreeMap<u64, RevocationRegistry>>,
+    match_unqualified: bool,
+}
+
+impl InMemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a resolver which also finds objects with qualified identifiers
+    /// by the unqualified forms of the identifiers. Objects are held by their
+    /// unqualified identifiers, so objects whose identifiers only differ in
+    /// their qualification replace each other.
+    pub fn with_unqualified_matching() -> Self {
+        Self {
+            match_unqualified: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn add_schema(&mut self, schema: Schema) {
+        self.schemas.insert(self.key(schema.id()), schema);
+    }
+
+    pub fn add_cred_def(&mut self, cred_def: CredentialDefinition) {
+        self.cred_defs.insert(self.key(cred_def.id()), cred_def);
+    }
+
+    pub fn add_rev_reg_def(&mut self, rev_reg_def: RevocationRegistryDefinition) {
+        self.rev_reg_defs
+            .insert(self.key(rev_reg_def.id()), rev_reg_def);
+    }
+
+    pub fn add_rev_reg(
+        &mut self,
+        rev_reg_id: &RevocationRegistryId,
+        timestamp: u64,
+        rev_reg: RevocationRegistry,
+    ) {
+        self.rev_regs
+            .entry(self.key(rev_reg_id))
+            .or_default()
+            .insert(timestamp, rev_reg);
+    }
+
+    fn key<K: Qualifiable + Clone>(&self, id: &K) -> K {
+        if self.match_unqualified {
+            id.to_unqualified()
+        } else {
+            id.clone()
+        }
+    }
+}
+
+impl LedgerResolver for InMemoryResolver {
+    fn resolve_schema(&self, id: &SchemaId) -> Result<Option<Resolved<'_, Schema>>> {
+        Ok(self.schemas.get(&self.key(id)).map(Resolved::Borrowed))
+    }
+
+    fn resolve_cred_def(
+        &self,
+        id: &CredentialDefinitionId,
+    ) -> Result<Option<Resolved<'_, CredentialDefinition>>> {
+        Ok(self.cred_defs.get(&self.key(id)).map(Resolved::Borrowed))
+    }
+
+    fn resolve_rev_reg_def(
+        &self,
+        id: &RevocationRegistryId,
+    ) -> Result<Option<Resolved<'_, RevocationRegistryDefinition>>> {
+        Ok(self.rev_reg_defs.get(&self.key(id)).map(Resolved::Borrowed))
+    }
+
+    fn resolve_rev_reg(
+        &self,
+        id: &RevocationRegistryId,
+        timestamp: u64,
+    ) -> Result<Option<Resolved<'_, RevocationRegistry>>> {
+        Ok(self
+            .rev_regs
+            .get(&self.key(id))
+            .and_then(|regs| regs.get(&timestamp))
+            .map(Resolved::Borrowed))
+    }
+
+    fn resolve_rev_reg_at(
+        &self,
+        id: &RevocationRegistryId,
+        timestamp: u64,
+    ) -> Result<Option<RegistryState<'_>>> {
+        Ok(self.rev_regs.get(&self.key(id)).and_then(|regs| {
+            registry_state_at(regs.iter().map(|(time, reg)| (*time, reg)), timestamp)
+        }))
+    }
+}
+
+/// A resolver over the ledger object maps accepted by the map-based
+/// verification and proving methods, which finds objects by their exact
+/// identifiers.
+pub(crate) struct MapResolver<'a> {
+    pub schemas: &'a HashMap<SchemaId, &'a Schema>,
+    pub cred_defs: &'a HashMap<CredentialDefinitionId, &'a CredentialDefinition>,
+    pub rev_reg_defs: Option<&'a HashMap<RevocationRegistryId, &'a RevocationRegistryDefinition>>,
+    pub rev_regs: Option<&'a HashMap<RevocationRegistryId, HashMap<u64, &'a RevocationRegistry>>>,
+}
+
+impl LedgerResolver for MapResolver<'_> {
+    fn resolve_schema(&self, id: &SchemaId) -> Result<Option<Resolved<'_, Schema>>> {
+        Ok(self.schemas.get(id).map(|obj| Resolved::Borrowed(*obj)))
+    }
+
+    fn resolve_cred_def(
+        &self,
+        id: &CredentialDefinitionId,
+    ) -> Result<Option<Resolved<'_, CredentialDefinition>>> {
+        Ok(self.cred_defs.get(id).map(|obj| Resolved::Borrowed(*obj)))
+    }
+
+    fn resolve_rev_reg_def(
+        &self,
+        id: &RevocationRegistryId,
+    ) -> Result<Option<Resolved<'_, RevocationRegistryDefinition>>> {
+        Ok(self
+            .rev_reg_defs
+            .and_then(|defs| defs.get(id))
+            .map(|obj| Resolved::Borrowed(*obj)))
+    }
+
+    fn resolve_rev_reg(
+        &self,
+        id: &RevocationRegistryId,
+        timestamp: u64,
+    ) -> Result<Option<Resolved<'_, RevocationRegistry>>> {
+        Ok(self
+            .rev_regs
+            .and_then(|regs| regs.get(id))
+            .and_then(|regs| regs.get(&timestamp))
+            .map(|obj| Resolved::Borrowed(*obj)))
+    }
+
+    fn resolve_rev_reg_at(
+        &self,
+        id: &RevocationRegistryId,
+        timestamp: u64,
+    ) -> Result<Option<RegistryState<'_>>> {
+        Ok(self
+            .rev_regs
+            .and_then(|regs| regs.get(id))
+            .and_then(|regs| {
+                registry_state_at(regs.iter().map(|(time, reg)| (*time, *reg)), timestamp)
+            }))
+    }
+}
+
+/// A resolver which keeps the objects returned by another resolver, so that
+/// each object is only fetched once. Objects which are borrowed from the inner
+/// resolver are cloned into the cache.
+#[derive(Debug)]
+pub struct CachingResolver<R> {
+    inner: R,
+    schemas: Mutex<HashMap<SchemaId, Arc<Schema>>>,
+    cred_defs: Mutex<HashMap<CredentialDefinitionId, Arc<CredentialDefinition>>>,
+    rev_reg_defs: Mutex<HashMap<RevocationRegistryId, Arc<RevocationRegistryDefinition>>>,
+    rev_regs: Mutex<HashMap<(RevocationRegistryId, u64), Arc<RevocationRegistry>>>,
+    rev_reg_states: Mutex<HashMap<(RevocationRegistryId, u64), CachedState>>,
+}
+
+/// A registry state returned by the inner resolver of a `CachingResolver`.
+#[derive(Debug)]
+struct CachedState {
+    rev_reg: Arc<RevocationRegistry>,
+    published: u64,
+    replaced: Option<u64>,
+}
+
+impl<R: LedgerResolver> CachingResolver<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            schemas: Mutex::default(),
+            cred_defs: Mutex::default(),
+            rev_reg_defs: Mutex::default(),
+            rev_regs: Mutex::default(),
+            rev_reg_states: Mutex::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Discard all cached objects.
+    pub fn clear(&self) {
+        lock(&self.schemas).clear();
+        lock(&self.cred_defs).clear();
+        lock(&self.rev_reg_defs).clear();
+        lock(&self.rev_regs).clear();
+        lock(&self.rev_reg_states).clear();
+    }
+}
+
+/// Ledger objects which a `CachingResolver` copies into its cache when they
+/// are borrowed from the inner resolver.
+trait CacheClone: Sized {
+    fn cache_clone(&self) -> Result<Self>;
+}
+
+impl CacheClone for Schema {
+    fn cache_clone(&self) -> Result<Self> {
+        Ok(self.clone())
+    }
+}
+
+impl CacheClone for CredentialDefinition {
+    fn cache_clone(&self) -> Result<Self> {
+        self.try_clone().map_err(err_map!(Unexpected))
+    }
+}
+
+impl CacheClone for RevocationRegistryDefinition {
+    fn cache_clone(&self) -> Result<Self> {
+        Ok(self.clone())
+    }
+}
+
+impl CacheClone for RevocationRegistry {
+    fn cache_clone(&self) -> Result<Self> {
+        Ok(self.clone())
+    }
+}
+
+fn into_shared<T: CacheClone>(resolved: Resolved<'_, T>) -> Result<Arc<T>> {
+    match resolved {
+        Resolved::Borrowed(obj) => Ok(Arc::new(obj.cache_clone()?)),
+        Resolved::Shared(obj) => Ok(obj),
+    }
+}
+
+fn cached<'r, K, T>(
+    cache: &Mutex<HashMap<K, Arc<T>>>,
+    key: K,
+    resolve: impl FnOnce() -> Result<Option<Resolved<'r, T>>>,
+) -> Result<Option<Resolved<'r, T>>>
+where
+    K: Eq + Hash,
+    T: CacheClone,
+{
+    if let Some(obj) = lock(cache).get(&key) {
+        return Ok(Some(Resolved::Shared(obj.clone())));
+    }
+    let Some(resolved) = resolve()? else {
+        return Ok(None);
+    };
+    let obj = into_shared(resolved)?;
+    lock(cache).insert(key, obj.clone());
+    Ok(Some(Resolved::Shared(obj)))
+}
+
+impl<R: LedgerResolver> LedgerResolver for CachingResolver<R> {
+    fn resolve_schema(&self, id: &SchemaId) -> Result<Option<Resolved<'_, Schema>>> {
+        cached(&self.schemas, id.clone(), || self.inner.resolve_schema(id))
+    }
+
+    fn resolve_cred_def(
+        &self,
+        id: &CredentialDefinitionId,
+    ) -> Result<Option<Resolved<'_, CredentialDefinition>>> {
+        cached(&self.cred_defs, id.clone(), || {
+            self.inner.resolve_cred_def(id)
+        })
+    }
+
+    fn resolve_rev_reg_def(
+        &self,
+        id: &RevocationRegistryId,
+    ) -> Result<Option<Resolved<'_, RevocationRegistryDefinition>>> {
+        cached(&self.rev_reg_defs, id.clone(), || {
+            self.inner.resolve_rev_reg_def(id)
+        })
+    }
+
+    fn resolve_rev_reg(
+        &self,
+        id: &RevocationRegistryId,
+        timestamp: u64,
+    ) -> Result<Option<Resolved<'_, RevocationRegistry>>> {
+        cached(&self.rev_regs, (id.clone(), timestamp), || {
+            self.inner.resolve_rev_reg(id, timestamp)
+        })
+    }
+
+    fn resolve_rev_reg_at(
+        &self,
+        id: &RevocationRegistryId,
+        timestamp: u64,
+    ) -> Result<Option<RegistryState<'_>>> {
+        let key = (id.clone(), timestamp);
+        if let Some(state) = lock(&self.rev_reg_states).get(&key) {
+            return Ok(Some(RegistryState {
+                rev_reg: Resolved::Shared(state.rev_reg.clone()),
+                published: state.published,
+                replaced: state.replaced,
+            }));
+        }
+        let Some(state) = self.inner.resolve_rev_reg_at(id, timestamp)? else {
+            return Ok(None);
+        };
+        let rev_reg = into_shared(state.rev_reg)?;
+        lock(&self.rev_reg_states).insert(
+            key,
+            CachedState {
+                rev_reg: rev_reg.clone(),
+                published: state.published,
+                replaced: state.replaced,
+            },
+        );
+        Ok(Some(RegistryState {
+            rev_reg: Resolved::Shared(rev_reg),
+            published: state.published,
+            replaced: state.replaced,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::services::issuer;
+
+    struct CountingResolver {
+        schema: Arc<Schema>,
+        shared: bool,
+        calls: Cell<usize>,
+    }
+
+    impl LedgerResolver for CountingResolver {
+        fn resolve_schema(&self, id: &SchemaId) -> Result<Option<Resolved<'_, Schema>>> {
+            self.calls.set(self.calls.get() + 1);
+            Ok((id == self.schema.id()).then(|| {
+                if self.shared {
+                    Resolved::Shared(self.schema.clone())
+                } else {
+                    Resolved::Borrowed(&*self.schema)
+                }
+            }))
+        }
+
+        fn resolve_cred_def(
+            &self,
+            _id: &CredentialDefinitionId,
+        ) -> Result<Option<Resolved<'_, CredentialDefinition>>> {
+            Ok(None)
+        }
+    }
+
+    fn schema(origin_did: &DidValue) -> Schema {
+        issuer::create_schema(
+            origin_did,
+            "test",
+            "1.0",
+            vec!["name".to_string()].into(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn in_memory_resolver_matches_unqualified_ids() {
+        let schema = schema(&DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", Some("sov")));
+        let schema_id = schema.id().clone();
+        assert!(schema_id.is_fully_qualified());
+
+        // identifiers are matched exactly by default
+        let mut resolver = InMemoryResolver::new();
+        resolver.add_schema(schema.clone());
+        assert!(resolver.resolve_schema(&schema_id).unwrap().is_some());
+        assert!(resolver
+            .resolve_schema(&schema_id.to_unqualified())
+            .unwrap()
+            .is_none());
+
+        let mut resolver = InMemoryResolver::with_unqualified_matching();
+        resolver.add_schema(schema);
+        assert!(resolver.resolve_schema(&schema_id).unwrap().is_some());
+        let resolved = resolver
+            .resolve_schema(&schema_id.to_unqualified())
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.id(), &schema_id);
+        assert!(resolver
+            .resolve_schema(&SchemaId::new(
+                &DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None),
+                "other",
+                "1.0"
+            ))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn caching_resolver_fetches_once() {
+        let schema = Arc::new(schema(&DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None)));
+        let schema_id = schema.id().clone();
+
+        // borrowed objects are cloned into the cache
+        for shared in [true, false] {
+            let resolver = CachingResolver::new(CountingResolver {
+                schema: schema.clone(),
+                shared,
+                calls: Cell::new(0),
+            });
+
+            for _ in 0..2 {
+                let resolved = resolver.resolve_schema(&schema_id).unwrap().unwrap();
+                assert!(matches!(resolved, Resolved::Shared(_)));
+            }
+            assert_eq!(resolver.inner().calls.get(), 1);
+
+            // unknown objects are not cached
+            let unknown = SchemaId::new(&DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None), "x", "1.0");
+            for _ in 0..2 {
+                assert!(resolver.resolve_schema(&unknown).unwrap().is_none());
+            }
+            assert_eq!(resolver.inner().calls.get(), 3);
+
+            resolver.clear();
+            assert!(resolver.resolve_schema(&schema_id).unwrap().is_some());
+            assert_eq!(resolver.inner().calls.get(), 4);
+        }
+    }
+}
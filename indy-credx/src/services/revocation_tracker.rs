@@ -0,0 +1,296 @@
+use std::collections::BTreeSet;
+
+use super::types::*;
+use crate::error::Error;
+
+/// Tracks the use of credential indices within a revocation registry.
+///
+/// Indices are allocated in order from 1 up to the maximum credential count of
+/// the registry. Revocations and un-revocations are collected until
+/// `take_pending` is called, which produces the `issued` and `revoked` sets
+/// expected by `issuer::update_revocation_registry`. The issuance type of the
+/// registry determines the status of indices which have not been allocated.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationRegistryTracker {
+    max_cred_num: u32,
+    issuance_type: IssuanceType,
+    next_index: u32,
+    issued: BTreeSet<u32>,
+    revoked: BTreeSet<u32>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pending_issued: BTreeSet<u32>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pending_revoked: BTreeSet<u32>,
+}
+
+impl RevocationRegistryTracker {
+    pub fn new(max_cred_num: u32, issuance_type: IssuanceType) -> Self {
+        Self {
+            max_cred_num,
+            issuance_type,
+            next_index: 1,
+            issued: BTreeSet::new(),
+            revoked: BTreeSet::new(),
+            pending_issued: BTreeSet::new(),
+            pending_revoked: BTreeSet::new(),
+        }
+    }
+
+    pub fn from_definition(rev_reg_def: &RevocationRegistryDefinition) -> Self {
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
+        Self::new(
+            rev_reg_def.value.max_cred_num,
+            rev_reg_def.value.issuance_type,
+        )
+    }
+
+    pub fn max_cred_num(&self) -> u32 {
+        self.max_cred_num
+    }
+
+    pub fn issuance_type(&self) -> IssuanceType {
+        self.issuance_type
+    }
+
+    /// The indices which have been allocated and are not revoked.
+    pub fn issued(&self) -> &BTreeSet<u32> {
+        &self.issued
+    }
+
+    /// The indices which have been allocated and revoked.
+    pub fn revoked(&self) -> &BTreeSet<u32> {
+        &self.revoked
+    }
+
+    /// Whether the credential with index `idx` (starting from 1) is active in
+    /// the registry. Indices which have not been allocated are active under
+    /// `ISSUANCE_BY_DEFAULT` and inactive under `ISSUANCE_ON_DEMAND`.
+    pub fn is_active(&self, idx: u32) -> bool {
+        if idx == 0 || idx > self.max_cred_num || self.revoked.contains(&idx) {
+            false
+        } else if idx < self.next_index {
+            true
+        } else {
+            self.issuance_type.to_bool()
+        }
+    }
+
+    /// The status of every index in the registry, where entry `i` is set when
+    /// the credential with index `i + 1` is not active, as in a published
+    /// revocation status list.
+    pub fn revocation_list(&self) -> Vec<bool> {
+        (1..=self.max_cred_num)
+            .map(|idx| !self.is_active(idx))
+            .collect()
+    }
+
+    /// The number of indices remaining to be allocated.
+    pub fn remaining(&self) -> u32 {
+        self.max_cred_num
+            .saturating_sub(self.next_index.saturating_sub(1))
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Allocate the next free index for a new credential.
+    pub fn allocate(&mut self) -> Result<u32, Error> {
+        if self.is_full() {
+            return Err(err_msg!(
+                RevocationRegistryFull,
+                "All {} indices of the revocation registry are in use",
+                self.max_cred_num
+            ));
+        }
+        let idx = self.next_index;
+        self.next_index += 1;
+        self.issued.insert(idx);
+        Ok(idx)
+    }
+
+    /// Record the revocation of an issued credential.
+    pub fn revoke(&mut self, idx: u32) -> Result<(), Error> {
+        if self.revoked.contains(&idx) {
+            return Err(err_msg!(
+                InvalidState,
+                "Credential index {} is already revoked",
+                idx
+            ));
+        }
+        if !self.issued.remove(&idx) {
+            return Err(err_msg!(
+                InvalidUserRevocId,
+                "Credential index {} has not been issued",
+                idx
+            ));
+        }
+        self.revoked.insert(idx);
+        if !self.pending_issued.remove(&idx) {
+            self.pending_revoked.insert(idx);
+        }
+        Ok(())
+    }
+
+    /// Record the restoration of a revoked credential.
+    pub fn unrevoke(&mut self, idx: u32) -> Result<(), Error> {
+        if !self.revoked.remove(&idx) {
+            return Err(err_msg!(
+                InvalidUserRevocId,
+                "Credential index {} is not revoked",
+                idx
+            ));
+        }
+        self.issued.insert(idx);
+        if !self.pending_revoked.remove(&idx) {
+            self.pending_issued.insert(idx);
+        }
+        Ok(())
+    }
+
+    /// Check whether there are changes which have not been applied to the registry.
+    pub fn has_pending(&self) -> bool {
+        !self.pending_issued.is_empty() || !self.pending_revoked.is_empty()
+    }
+
+    /// Take the `issued` and `revoked` sets to be applied to the registry
+    /// with `issuer::update_revocation_registry`.
+    pub fn take_pending(&mut self) -> (BTreeSet<u32>, BTreeSet<u32>) {
+        (
+            std::mem::take(&mut self.pending_issued),
+            std::mem::take(&mut self.pending_revoked),
+        )
+    }
+}
+
+impl Validatable for RevocationRegistryTracker {
+    fn validate(&self) -> std::result::Result<(), ValidationError> {
+        if self.next_index == 0 || self.next_index > self.max_cred_num.saturating_add(1) {
+            return Err(invalid!("Invalid next index for revocation registry"));
+        }
+        if let Some(idx) = self.issued.intersection(&self.revoked).next() {
+            return Err(invalid!(
+                "Credential index {} is both issued and revoked",
+                idx
+            ));
+        }
+        if self.issued.len() + self.revoked.len() != self.next_index as usize - 1
+            || self
+                .issued
+                .iter()
+                .chain(self.revoked.iter())
+                .any(|idx| *idx == 0 || *idx >= self.next_index)
+        {
+            return Err(invalid!(
+                "Inconsistent credential indices for revocation registry"
+            ));
+        }
+        if !self.pending_issued.is_subset(&self.issued)
+            || !self.pending_revoked.is_subset(&self.revoked)
+        {
+            return Err(invalid!("Inconsistent pending revocation registry changes"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracker_allocates_until_full() {
+        let mut tracker = RevocationRegistryTracker::new(2, IssuanceType::ISSUANCE_BY_DEFAULT);
+        assert_eq!(tracker.allocate().unwrap(), 1);
+        assert_eq!(tracker.allocate().unwrap(), 2);
+        assert!(tracker.is_full());
+        assert_kind!(RevocationRegistryFull, tracker.allocate());
+        assert_eq!(tracker.issued(), &BTreeSet::from([1, 2]));
+        tracker.validate().unwrap();
+    }
+
+    #[test]
+    fn tracker_collects_pending_changes() {
+        let mut tracker = RevocationRegistryTracker::new(5, IssuanceType::ISSUANCE_ON_DEMAND);
+        for _ in 0..3 {
+            tracker.allocate().unwrap();
+        }
+        assert_kind!(InvalidUserRevocId, tracker.revoke(4));
+        assert_kind!(InvalidUserRevocId, tracker.unrevoke(1));
+
+        tracker.revoke(1).unwrap();
+        tracker.revoke(2).unwrap();
+        assert_kind!(InvalidState, tracker.revoke(2));
+        tracker.unrevoke(2).unwrap();
+        assert_eq!(
+            tracker.take_pending(),
+            (BTreeSet::new(), BTreeSet::from([1]))
+        );
+        assert!(!tracker.has_pending());
+
+        tracker.unrevoke(1).unwrap();
+        tracker.revoke(3).unwrap();
+        assert_eq!(
+            tracker.take_pending(),
+            (BTreeSet::from([1]), BTreeSet::from([3]))
+        );
+        assert_eq!(tracker.issued(), &BTreeSet::from([1, 2]));
+        assert_eq!(tracker.revoked(), &BTreeSet::from([3]));
+        assert_eq!(tracker.remaining(), 2);
+    }
+
+    #[test]
+    fn tracker_reports_status_by_issuance_type() {
+        for (issuance_type, unallocated) in [
+            (IssuanceType::ISSUANCE_BY_DEFAULT, false),
+            (IssuanceType::ISSUANCE_ON_DEMAND, true),
+        ] {
+            let mut tracker = RevocationRegistryTracker::new(4, issuance_type);
+            tracker.allocate().unwrap();
+            tracker.allocate().unwrap();
+            tracker.revoke(2).unwrap();
+            assert!(tracker.is_active(1));
+            assert!(!tracker.is_active(2));
+            assert_eq!(tracker.is_active(3), !unallocated);
+            assert!(!tracker.is_active(0));
+            assert!(!tracker.is_active(5));
+            assert_eq!(
+                tracker.revocation_list(),
+                [false, true, unallocated, unallocated]
+            );
+        }
+    }
+
+    #[test]
+    fn tracker_serialization() {
+        let mut tracker = RevocationRegistryTracker::new(5, IssuanceType::ISSUANCE_BY_DEFAULT);
+        tracker.allocate().unwrap();
+        tracker.allocate().unwrap();
+        tracker.revoke(2).unwrap();
+        let json = serde_json::to_value(&tracker).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "max_cred_num": 5,
+                "issuance_type": "ISSUANCE_BY_DEFAULT",
+                "next_index": 3,
+                "issued": [1],
+                "revoked": [2],
+                "pending_revoked": [2],
+            })
+        );
+        let restored: RevocationRegistryTracker = serde_json::from_value(json).unwrap();
+        restored.validate().unwrap();
+        assert_eq!(restored, tracker);
+
+        let invalid: RevocationRegistryTracker = serde_json::from_value(serde_json::json!({
+            "max_cred_num": 5,
+            "issuance_type": "ISSUANCE_BY_DEFAULT",
+            "next_index": 2,
+            "issued": [1, 2],
+            "revoked": [],
+        }))
+        .unwrap();
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,369 @@
+use std::collections::HashSet;
+
+use super::helpers::revocation_registry_delta_parts;
+use super::types::*;
+use crate::anoncreds_clsignatures::{
+    Accumulator, RevocationRegistry as CryptoRevocationRegistry,
+    RevocationRegistryDelta as CryptoRevocationRegistryDelta,
+};
+use crate::error::Result;
+use indy_data_types::anoncreds::rev_reg::{RevocationRegistryDeltaV1, RevocationRegistryV1};
+
+/// The revocation status of every credential in a registry along with the
+/// current accumulator, as published by AnonCreds ledgers.
+///
+/// Entry `i` of the list is set when the credential with index `i + 1` is
+/// not active. Under `ISSUANCE_ON_DEMAND`, indices which have not been issued
+/// are set as well as revoked indices, so the two cannot be told apart.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevocationStatusList {
+    issuer_id: DidValue,
+    rev_reg_def_id: RevocationRegistryId,
+    #[serde(with = "serde_revocation_list")]
+    revocation_list: Vec<bool>,
+    current_accumulator: Accumulator,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<u64>,
+}
+
+impl RevocationStatusList {
+    pub fn new(
+        rev_reg_def: &RevocationRegistryDefinition,
+        revocation_list: Vec<bool>,
+        current_accumulator: Accumulator,
+        timestamp: Option<u64>,
+    ) -> Result<Self> {
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def) = rev_reg_def;
+        if revocation_list.len() != def.value.max_cred_num as usize {
+            return Err(err_msg!(
+                "Status list length {} does not match the registry size {}",
+                revocation_list.len(),
+                def.value.max_cred_num
+            ));
+        }
+        Ok(Self {
+            issuer_id: issuer_id(&def.id)?,
+            rev_reg_def_id: def.id.clone(),
+            revocation_list,
+            current_accumulator,
+            timestamp,
+        })
+    }
+
+    /// Create the status list from a delta which describes the registry from
+    /// its initial state, such as the initial delta of the registry merged
+    /// with every later delta.
+    pub fn from_registry_delta(
+        rev_reg_def: &RevocationRegistryDefinition,
+        delta: &RevocationRegistryDelta,
+        timestamp: Option<u64>,
+    ) -> Result<Self> {
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def) = rev_reg_def;
+        let parts = revocation_registry_delta_parts(delta)?;
+        if parts.prev_accum.is_some() {
+            return Err(err_msg!(
+                "Revocation registry delta does not start from the initial registry state"
+            ));
+        }
+        let max_cred_num = def.value.max_cred_num;
+        if let Some(idx) = parts
+            .issued
+            .iter()
+            .chain(parts.revoked.iter())
+            .find(|idx| **idx == 0 || **idx > max_cred_num)
+        {
+            return Err(err_msg!(
+                InvalidUserRevocId,
+                "Revocation index {} is outside of the range 1..={}",
+                idx,
+                max_cred_num
+            ));
+        }
+        let revocation_list = (1..=max_cred_num)
+            .map(|idx| match def.value.issuance_type {
+                IssuanceType::ISSUANCE_BY_DEFAULT => parts.revoked.contains(&idx),
+                IssuanceType::ISSUANCE_ON_DEMAND => !parts.issued.contains(&idx),
+            })
+            .collect();
+        Ok(Self {
+            issuer_id: issuer_id(&def.id)?,
+            rev_reg_def_id: def.id.clone(),
+            revocation_list,
+            current_accumulator: parts.accum,
+            timestamp,
+        })
+    }
+
+    /// The identifier of the issuer of the revocation registry.
+    pub fn issuer_id(&self) -> &DidValue {
+        &self.issuer_id
+    }
+
+    pub fn rev_reg_def_id(&self) -> &RevocationRegistryId {
+        &self.rev_reg_def_id
+    }
+
+    pub fn revocation_list(&self) -> &[bool] {
+        &self.revocation_list
+    }
+
+    pub fn current_accumulator(&self) -> &Accumulator {
+        &self.current_accumulator
+    }
+
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+
+    /// Whether the credential with index `idx` (starting from 1) is not active.
+    pub fn is_revoked(&self, idx: u32) -> bool {
+        idx == 0
+            || self
+                .revocation_list
+                .get(idx as usize - 1)
+                .copied()
+                .unwrap_or(true)
+    }
+
+    /// The registry state and the delta describing it from the initial state
+    /// of the registry.
+    ///
+    /// Under `ISSUANCE_ON_DEMAND` the delta lists only the active indices as
+    /// issued, which is sufficient for updating witnesses.
+    pub fn to_registry_delta(
+        &self,
+        rev_reg_def: &RevocationRegistryDefinition,
+    ) -> Result<(RevocationRegistry, RevocationRegistryDelta)> {
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def) = rev_reg_def;
+        self.check_definition(def.id.clone(), def.value.max_cred_num)?;
+        let mut issued = HashSet::new();
+        let mut revoked = HashSet::new();
+        for idx in 1..=def.value.max_cred_num {
+            match (def.value.issuance_type, self.is_revoked(idx)) {
+                (IssuanceType::ISSUANCE_BY_DEFAULT, true) => {
+                    revoked.insert(idx);
+                }
+                (IssuanceType::ISSUANCE_ON_DEMAND, false) => {
+                    issued.insert(idx);
+                }
+                _ => {}
+            }
+        }
+        let registry = CryptoRevocationRegistry::from(self.current_accumulator);
+        let delta = CryptoRevocationRegistryDelta::from_parts(None, &registry, &issued, &revoked);
+        Ok((
+            RevocationRegistry::RevocationRegistryV1(RevocationRegistryV1 { value: registry }),
+            RevocationRegistryDelta::RevocationRegistryDeltaV1(RevocationRegistryDeltaV1 {
+                value: delta,
+            }),
+        ))
+    }
+
+    /// The delta which updates the registry from the state of this status
+    /// list to the state of a later status list for the same registry.
+    pub fn delta_to(&self, next: &RevocationStatusList) -> Result<RevocationRegistryDelta> {
+        next.check_definition(
+            self.rev_reg_def_id.clone(),
+            self.revocation_list.len() as u32,
+        )?;
+        let mut issued = HashSet::new();
+        let mut revoked = HashSet::new();
+        for (idx, (prev, next)) in
+            (1..).zip(self.revocation_list.iter().zip(next.revocation_list.iter()))
+        {
+            match (prev, next) {
+                (true, false) => {
+                    issued.insert(idx);
+                }
+                (false, true) => {
+                    revoked.insert(idx);
+                }
+                _ => {}
+            }
+        }
+        Ok(RevocationRegistryDelta::RevocationRegistryDeltaV1(
+            RevocationRegistryDeltaV1 {
+                value: CryptoRevocationRegistryDelta::from_parts(
+                    Some(&CryptoRevocationRegistry::from(self.current_accumulator)),
+                    &CryptoRevocationRegistry::from(next.current_accumulator),
+                    &issued,
+                    &revoked,
+                ),
+            },
+        ))
+    }
+
+    /// Apply a delta published after this status list, producing the status
+    /// list for the updated registry.
+    pub fn apply_delta(
+        &self,
+        delta: &RevocationRegistryDelta,
+        timestamp: Option<u64>,
+    ) -> Result<RevocationStatusList> {
+        let parts = revocation_registry_delta_parts(delta)?;
+        if parts.prev_accum != Some(self.current_accumulator) {
+            return Err(err_msg!(
+                "Revocation registry delta does not apply to the status list accumulator"
+            ));
+        }
+        let mut revocation_list = self.revocation_list.clone();
+        for (indices, revoked) in [(&parts.issued, false), (&parts.revoked, true)] {
+            for idx in indices {
+                let entry = idx
+                    .checked_sub(1)
+                    .and_then(|pos| revocation_list.get_mut(pos as usize))
+                    .ok_or_else(|| {
+                        err_msg!(
+                            InvalidUserRevocId,
+                            "Revocation index {} is outside of the status list",
+                            idx
+                        )
+                    })?;
+                *entry = revoked;
+            }
+        }
+        Ok(RevocationStatusList {
+            issuer_id: self.issuer_id.clone(),
+            rev_reg_def_id: self.rev_reg_def_id.clone(),
+            revocation_list,
+            current_accumulator: parts.accum,
+            timestamp,
+        })
+    }
+
+    fn check_definition(&self, rev_reg_def_id: RevocationRegistryId, size: u32) -> Result<()> {
+        if self.rev_reg_def_id != rev_reg_def_id {
+            return Err(err_msg!(
+                "Status list is for the revocation registry {}, not {}",
+                self.rev_reg_def_id,
+                rev_reg_def_id
+            ));
+        }
+        if self.revocation_list.len() != size as usize {
+            return Err(err_msg!(
+                "Status list length {} does not match the registry size {}",
+                self.revocation_list.len(),
+                size
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn issuer_id(rev_reg_def_id: &RevocationRegistryId) -> Result<DidValue> {
+    rev_reg_def_id
+        .parts()
+        .map(|(did, _, _, _)| did)
+        .ok_or_else(|| {
+            err_msg!(
+                "Cannot determine the issuer of the revocation registry {}",
+                rev_reg_def_id
+            )
+        })
+}
+
+mod serde_revocation_list {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(list: &[bool], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(list.iter().map(|revoked| *revoked as u8))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<bool>, D::Error> {
+        Vec::<u8>::deserialize(deserializer)?
+            .into_iter()
+            .map(|bit| match bit {
+                0 => Ok(false),
+                1 => Ok(true),
+                _ => Err(D::Error::custom("Revocation list entries must be 0 or 1")),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::issuer;
+    use crate::services::test_support::{accum, create_registry, parts, TestRegistry};
+
+    #[test]
+    fn status_list_converts_registry_deltas() {
+        let TestRegistry {
+            cred_def,
+            rev_reg_def,
+            rev_reg_def_private,
+            registry: initial_reg,
+            initial_delta,
+            ..
+        } = create_registry(IssuanceType::ISSUANCE_BY_DEFAULT);
+        let (reg_1, delta_1) = issuer::revoke_credential(
+            &cred_def,
+            &rev_reg_def,
+            &rev_reg_def_private,
+            &initial_reg,
+            2,
+        )
+        .unwrap();
+
+        let initial =
+            RevocationStatusList::from_registry_delta(&rev_reg_def, &initial_delta, Some(10))
+                .unwrap();
+        assert_eq!(initial.revocation_list(), [false; 5]);
+        assert_eq!(initial.current_accumulator(), &accum(&initial_reg));
+
+        let merged = issuer::merge_revocation_registry_deltas(&initial_delta, &delta_1).unwrap();
+        let list =
+            RevocationStatusList::from_registry_delta(&rev_reg_def, &merged, Some(20)).unwrap();
+        assert!(list.is_revoked(2));
+        assert!(!list.is_revoked(1));
+        assert_eq!(list.current_accumulator(), &accum(&reg_1));
+        assert_kind!(
+            Input,
+            RevocationStatusList::from_registry_delta(&rev_reg_def, &delta_1, None)
+        );
+
+        let (registry, delta) = list.to_registry_delta(&rev_reg_def).unwrap();
+        assert_eq!(accum(&registry), accum(&reg_1));
+        let delta = parts(&delta);
+        assert_eq!(delta.prev_accum, None);
+        assert!(delta.issued.is_empty());
+        assert_eq!(delta.revoked, [2].into());
+
+        let linking = parts(&initial.delta_to(&list).unwrap());
+        assert_eq!(linking.prev_accum, Some(accum(&initial_reg)));
+        assert_eq!(linking.accum, accum(&reg_1));
+        assert!(linking.issued.is_empty());
+        assert_eq!(linking.revoked, [2].into());
+        assert_eq!(initial.apply_delta(&delta_1, Some(20)).unwrap(), list);
+        assert_kind!(Input, list.apply_delta(&delta_1, None));
+    }
+
+    #[test]
+    fn status_list_on_demand_serialization() {
+        let TestRegistry {
+            rev_reg_def,
+            initial_delta,
+            ..
+        } = create_registry(IssuanceType::ISSUANCE_ON_DEMAND);
+        let list =
+            RevocationStatusList::from_registry_delta(&rev_reg_def, &initial_delta, None).unwrap();
+        assert_eq!(list.revocation_list(), [true; 5]);
+        let (_, delta) = list.to_registry_delta(&rev_reg_def).unwrap();
+        assert!(parts(&delta).issued.is_empty());
+
+        let mut json = serde_json::to_value(&list).unwrap();
+        assert_eq!(json["issuerId"], "NcYxiDXkpYi6ov5FcYDi1e");
+        assert_eq!(json["revocationList"], serde_json::json!([1, 1, 1, 1, 1]));
+        assert_eq!(
+            serde_json::from_value::<RevocationStatusList>(json.clone()).unwrap(),
+            list
+        );
+        let mut missing_issuer = json.clone();
+        missing_issuer.as_object_mut().unwrap().remove("issuerId");
+        assert!(serde_json::from_value::<RevocationStatusList>(missing_issuer).is_err());
+        json["revocationList"] = serde_json::json!([0, 1, 2, 0, 0]);
+        assert!(serde_json::from_value::<RevocationStatusList>(json).is_err());
+    }
+}
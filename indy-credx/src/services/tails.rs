@@ -3,11 +3,15 @@ use std::fmt::Debug;
 use std::fs::File;
 use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use indy_data_types::utils::base58;
+use memmap2::Mmap;
 use rand::random;
 use sha2::{Digest, Sha256};
 
+use super::types::RevocationRegistryDefinition;
 use crate::anoncreds_clsignatures::{
     Error as ClError, ErrorKind as ClErrorKind, RevocationTailsAccessor, RevocationTailsGenerator,
     Tail,
@@ -30,6 +34,16 @@ impl TailsFileReader {
         }
     }
 
+    /// Create a reader for a tails file after checking it against the registry definition.
+    pub fn new_verified<P: Into<PathBuf>>(
+        path: P,
+        rev_reg_def: &RevocationRegistryDefinition,
+    ) -> Result<Self, Error> {
+        let path = path.into();
+        verify_tails_file(&path, rev_reg_def)?;
+        Ok(Self::new(path))
+    }
+
     fn opened(&self) -> Result<RefMut<'_, BufReader<File>>, io::Error> {
         let mut inner = self.file.borrow_mut();
         if inner.is_none() {
@@ -80,6 +94,228 @@ impl RevocationTailsAccessor for TailsFileReader {
     }
 }
 
+/// A read-only, memory-mapped tails file.
+///
+/// The file length and version tag are checked when the file is opened, after
+/// which tails are served directly from the mapping. The reader is `Send` and
+/// `Sync`, so a single instance may be shared between threads in an `Arc`.
+pub struct TailsMmapReader {
+    map: Mmap,
+    path: PathBuf,
+}
+
+impl TailsMmapReader {
+    /// Open and map a tails file.
+    ///
+    /// The caller must ensure that the file is not truncated or modified while
+    /// the reader, or any clone of its `Arc`, is alive. Truncating a mapped file
+    /// causes the process to receive `SIGBUS` when the removed pages are read,
+    /// and other modifications change the tails returned by the reader.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self, Error> {
+        let path = path.into();
+        let file = File::open(&path)
+            .map_err(|e| err_msg!(IOError, "Error opening tails file {path:?}: {e}"))?;
+        // SAFETY: the mapping is read-only, and the caller guarantees that the
+        // file is not truncated or modified while it is mapped.
+        let map = unsafe { Mmap::map(&file) }
+            .map_err(|e| err_msg!(IOError, "Error mapping tails file {path:?}: {e}"))?;
+        check_tails_blob(&map)?;
+        Ok(Self { map, path })
+    }
+
+    /// Open a tails file after checking it against the registry definition.
+    ///
+    /// The file must not be truncated or modified while it is mapped, as
+    /// described for [`TailsMmapReader::open`].
+    pub fn open_verified<P: Into<PathBuf>>(
+        path: P,
+        rev_reg_def: &RevocationRegistryDefinition,
+    ) -> Result<Self, Error> {
+        let reader = Self::open(path)?;
+        verify_tails(&reader.map, rev_reg_def)?;
+        Ok(reader)
+    }
+
+    /// The number of tails contained in the file.
+    pub fn tails_count(&self) -> u32 {
+        blob_tails_count(&self.map)
+    }
+}
+
+impl Debug for TailsMmapReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TailsMmapReader")
+            .field("path", &self.path)
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
+
+impl RevocationTailsAccessor for TailsMmapReader {
+    fn access_tail(
+        &self,
+        tail_id: u32,
+        accessor: &mut dyn FnMut(&Tail),
+    ) -> std::result::Result<(), ClError> {
+        trace!("access_tail >>> tail_id: {:?}", tail_id);
+
+        let tail = blob_tail(&self.map, tail_id)?;
+        accessor(&tail);
+
+        trace!("access_tail <<< res: ()");
+        Ok(())
+    }
+}
+
+/// A tails blob held in memory, as produced by `TailsBufferWriter`.
+pub struct TailsBufferReader {
+    tails: Vec<u8>,
+}
+
+impl TailsBufferReader {
+    pub fn new(tails: Vec<u8>) -> Result<Self, Error> {
+        check_tails_blob(&tails)?;
+        Ok(Self { tails })
+    }
+
+    /// Create a reader after checking the tails against the registry definition.
+    pub fn new_verified(
+        tails: Vec<u8>,
+        rev_reg_def: &RevocationRegistryDefinition,
+    ) -> Result<Self, Error> {
+        verify_tails(&tails, rev_reg_def)?;
+        Ok(Self { tails })
+    }
+
+    /// The number of tails contained in the buffer.
+    pub fn tails_count(&self) -> u32 {
+        blob_tails_count(&self.tails)
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.tails
+    }
+}
+
+impl Debug for TailsBufferReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TailsBufferReader")
+            .field("len", &self.tails.len())
+            .finish()
+    }
+}
+
+impl RevocationTailsAccessor for TailsBufferReader {
+    fn access_tail(
+        &self,
+        tail_id: u32,
+        accessor: &mut dyn FnMut(&Tail),
+    ) -> std::result::Result<(), ClError> {
+        trace!("access_tail >>> tail_id: {:?}", tail_id);
+
+        let tail = blob_tail(&self.tails, tail_id)?;
+        accessor(&tail);
+
+        trace!("access_tail <<< res: ()");
+        Ok(())
+    }
+}
+
+/// Verify a tails file against a revocation registry definition.
+///
+/// The file must have the expected length for the maximum credential count of
+/// the registry, begin with the supported version tag, and match the tails hash.
+pub fn verify_tails_file<P: AsRef<Path>>(
+    path: P,
+    rev_reg_def: &RevocationRegistryDefinition,
+) -> Result<(), Error> {
+    let path = path.as_ref();
+    let (max_cred_num, tails_hash) = registry_tails_info(rev_reg_def);
+    let mut file = File::open(path)
+        .map_err(|e| err_msg!(IOError, "Error opening tails file {path:?}: {e}"))?;
+    check_tails_len(file.metadata()?.len(), max_cred_num)?;
+    let mut version = [0u8; TAILS_BLOB_TAG_SZ as usize];
+    file.read_exact(&mut version)?;
+    check_tails_version(&version)?;
+    let mut hasher = Sha256::default();
+    hasher.update(version);
+    io::copy(&mut file, &mut hasher)?;
+    check_tails_hash(&base58::encode(hasher.finalize()), tails_hash)
+}
+
+/// Verify an in-memory tails blob against a revocation registry definition.
+pub fn verify_tails(tails: &[u8], rev_reg_def: &RevocationRegistryDefinition) -> Result<(), Error> {
+    let (max_cred_num, tails_hash) = registry_tails_info(rev_reg_def);
+    check_tails_len(tails.len() as u64, max_cred_num)?;
+    check_tails_version(tails)?;
+    check_tails_hash(&base58::encode(Sha256::digest(tails)), tails_hash)
+}
+
+fn registry_tails_info(rev_reg_def: &RevocationRegistryDefinition) -> (u32, &str) {
+    let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
+    (
+        rev_reg_def.value.max_cred_num,
+        rev_reg_def.value.tails_hash.as_str(),
+    )
+}
+
+fn check_tails_len(len: u64, max_cred_num: u32) -> Result<(), Error> {
+    let expected = TAILS_BLOB_TAG_SZ as u64 + TAIL_SIZE as u64 * (2 * max_cred_num as u64 + 1);
+    if len != expected {
+        return Err(err_msg!(
+            InvalidTailsFile,
+            "Tails file length {len} does not match the expected length {expected}"
+        ));
+    }
+    Ok(())
+}
+
+fn check_tails_version(blob: &[u8]) -> Result<(), Error> {
+    if blob.get(..TAILS_BLOB_TAG_SZ as usize) != Some(&[0u8, 2u8]) {
+        return Err(err_msg!(InvalidTailsFile, "Unsupported tails file version"));
+    }
+    Ok(())
+}
+
+fn check_tails_hash(hash: &str, expected: &str) -> Result<(), Error> {
+    if hash != expected {
+        return Err(err_msg!(
+            InvalidTailsFile,
+            "Tails hash {hash} does not match the registry definition hash {expected}"
+        ));
+    }
+    Ok(())
+}
+
+/// Check the length and version tag of a complete tails blob.
+fn check_tails_blob(blob: &[u8]) -> Result<(), Error> {
+    if blob.len() < TAILS_BLOB_TAG_SZ as usize + TAIL_SIZE
+        || (blob.len() - TAILS_BLOB_TAG_SZ as usize) % TAIL_SIZE != 0
+    {
+        return Err(err_msg!(
+            InvalidTailsFile,
+            "Invalid tails file length: {}",
+            blob.len()
+        ));
+    }
+    check_tails_version(blob)
+}
+
+fn blob_tails_count(blob: &[u8]) -> u32 {
+    ((blob.len() - TAILS_BLOB_TAG_SZ as usize) / TAIL_SIZE) as u32
+}
+
+fn blob_tail(blob: &[u8], tail_id: u32) -> std::result::Result<Tail, ClError> {
+    let start = TAIL_SIZE * tail_id as usize + TAILS_BLOB_TAG_SZ as usize;
+    let tail_bytes = blob.get(start..start + TAIL_SIZE).ok_or_else(|| {
+        ClError::new(
+            ClErrorKind::InvalidState,
+            format!("Tail index out of range: {tail_id}"),
+        )
+    })?;
+    Tail::from_bytes(tail_bytes)
+}
+
 pub trait TailsWriter: std::fmt::Debug {
     fn write(
         &mut self,
@@ -87,9 +323,93 @@ pub trait TailsWriter: std::fmt::Debug {
     ) -> Result<(String, String), Error>;
 }
 
+/// A token used to cancel tails generation, possibly from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Progress reporting and cancellation shared by the tails writers.
+///
+/// The callback is invoked before the first tail is written, after every
+/// hundredth of the tails, and always once all of the tails are written.
+#[derive(Default)]
+struct TailsProgress {
+    callback: Option<Box<dyn FnMut(u32, u32) + Send>>,
+    cancel: Option<CancellationToken>,
+    last_reported: Option<u32>,
+}
+
+impl TailsProgress {
+    /// Generate the tails blob, passing each chunk to `output`.
+    fn generate(
+        &mut self,
+        generator: &mut RevocationTailsGenerator,
+        mut output: impl FnMut(&[u8]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let total = generator.count();
+        self.last_reported = None;
+        output(&[0u8, 2u8])?;
+        self.report(0, total)?;
+        let mut written = 0;
+        while let Some(tail) = generator.try_next()? {
+            output(&tail.to_bytes()?)?;
+            written += 1;
+            self.report(written, total)?;
+        }
+        Ok(())
+    }
+
+    fn report(&mut self, written: u32, total: u32) -> Result<(), Error> {
+        if self.should_report(written, total) {
+            self.last_reported = Some(written);
+            if let Some(callback) = self.callback.as_mut() {
+                callback(written, total);
+            }
+        }
+        if written < total && self.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+            return Err(err_msg!(
+                Cancelled,
+                "Tails generation cancelled after {written} of {total} tails"
+            ));
+        }
+        Ok(())
+    }
+
+    fn should_report(&self, written: u32, total: u32) -> bool {
+        let step = (total / 100).max(1);
+        match self.last_reported {
+            None => true,
+            Some(last) => written >= total || written - last >= step,
+        }
+    }
+}
+
+impl Debug for TailsProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TailsProgress")
+            .field("callback", &self.callback.is_some())
+            .field("cancel", &self.cancel)
+            .finish()
+    }
+}
+
 #[derive(Debug)]
 pub struct TailsFileWriter {
     root_path: PathBuf,
+    progress: TailsProgress,
 }
 
 impl TailsFileWriter {
@@ -98,8 +418,22 @@ impl TailsFileWriter {
             root_path: root_path
                 .map(PathBuf::from)
                 .unwrap_or_else(std::env::temp_dir),
+            progress: TailsProgress::default(),
         }
     }
+
+    /// Report the number of tails written out of the total during generation,
+    /// at most once per hundredth of the tails and always on completion.
+    pub fn with_progress(mut self, callback: impl FnMut(u32, u32) + Send + 'static) -> Self {
+        self.progress.callback.replace(Box::new(callback));
+        self
+    }
+
+    /// Abort generation and remove the temporary file when the token is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.progress.cancel.replace(token);
+        self
+    }
 }
 
 impl TailsWriter for TailsFileWriter {
@@ -134,14 +468,11 @@ impl TailsWriter for TailsFileWriter {
         let temp_handle = TempFile(&temp_path);
         let mut buf = BufWriter::new(file);
         let mut hasher = Sha256::default();
-        let version = &[0u8, 2u8];
-        buf.write_all(version)?;
-        hasher.update(version);
-        while let Some(tail) = generator.try_next()? {
-            let tail_bytes = tail.to_bytes()?;
-            buf.write_all(&tail_bytes)?;
-            hasher.update(&tail_bytes);
-        }
+        self.progress.generate(generator, |bytes| {
+            buf.write_all(bytes)?;
+            hasher.update(bytes);
+            Ok(())
+        })?;
         let mut file = buf
             .into_inner()
             .map_err(|e| err_msg!("Error flushing output file: {e}"))?;
@@ -158,3 +489,330 @@ impl TailsWriter for TailsFileWriter {
         Ok((target_path, hash))
     }
 }
+
+/// A `TailsWriter` which collects the tails blob in memory.
+///
+/// The returned tails location is the hash of the blob, appended to the base
+/// location when one is provided. The blob itself may be retrieved with
+/// `tails` or `into_tails` once the registry has been created.
+#[derive(Debug, Default)]
+pub struct TailsBufferWriter {
+    base_location: Option<String>,
+    tails: Vec<u8>,
+    hash: Option<String>,
+    progress: TailsProgress,
+}
+
+impl TailsBufferWriter {
+    pub fn new(base_location: Option<String>) -> Self {
+        Self {
+            base_location,
+            ..Default::default()
+        }
+    }
+
+    /// Report the number of tails written out of the total during generation,
+    /// at most once per hundredth of the tails and always on completion.
+    pub fn with_progress(mut self, callback: impl FnMut(u32, u32) + Send + 'static) -> Self {
+        self.progress.callback.replace(Box::new(callback));
+        self
+    }
+
+    /// Abort generation when the token is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.progress.cancel.replace(token);
+        self
+    }
+
+    /// The tails blob, if one has been written.
+    pub fn tails(&self) -> Option<&[u8]> {
+        self.hash.as_ref().map(|_| self.tails.as_slice())
+    }
+
+    /// The base58-encoded SHA-256 hash of the tails blob, if one has been written.
+    pub fn tails_hash(&self) -> Option<&str> {
+        self.hash.as_deref()
+    }
+
+    /// Take the tails blob and its hash, if one has been written.
+    pub fn into_tails(self) -> Option<(Vec<u8>, String)> {
+        let tails = self.tails;
+        self.hash.map(|hash| (tails, hash))
+    }
+}
+
+impl TailsWriter for TailsBufferWriter {
+    fn write(
+        &mut self,
+        generator: &mut RevocationTailsGenerator,
+    ) -> Result<(String, String), Error> {
+        let mut tails =
+            Vec::with_capacity(TAILS_BLOB_TAG_SZ as usize + TAIL_SIZE * generator.count() as usize);
+        self.progress.generate(generator, |bytes| {
+            tails.extend_from_slice(bytes);
+            Ok(())
+        })?;
+        let hash = base58::encode(Sha256::digest(&tails));
+        let location = match self.base_location.as_deref() {
+            Some(base) if base.ends_with('/') => format!("{base}{hash}"),
+            Some(base) => format!("{base}/{hash}"),
+            None => hash.clone(),
+        };
+        debug!(
+            "TailsBufferWriter: wrote tails [size {}]: {}",
+            tails.len(),
+            location
+        );
+        self.tails = tails;
+        self.hash.replace(hash.clone());
+        Ok((location, hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    use crate::anoncreds_clsignatures::Issuer as ClIssuer;
+    use crate::services::helpers::{build_credential_schema, build_non_credential_schema};
+    use crate::services::issuer;
+    use crate::services::types::{
+        CredentialDefinitionConfig, DidValue, IssuanceType, RegistryType, SignatureType,
+    };
+
+    fn tails_generator(max_cred_num: u32) -> RevocationTailsGenerator {
+        let cred_schema =
+            build_credential_schema(&[String::from("name")].into_iter().collect()).unwrap();
+        let non_cred_schema = build_non_credential_schema().unwrap();
+        let (cred_pub_key, _, _) =
+            ClIssuer::new_credential_def(&cred_schema, &non_cred_schema, true).unwrap();
+        let (_, _, _, generator) =
+            ClIssuer::new_revocation_registry_def(&cred_pub_key, max_cred_num, true).unwrap();
+        generator
+    }
+
+    fn write_tails_file(max_cred_num: u32) -> (String, String) {
+        let mut writer = TailsFileWriter::new(None);
+        writer.write(&mut tails_generator(max_cred_num)).unwrap()
+    }
+
+    fn registry_with_tails(max_cred_num: u32) -> (RevocationRegistryDefinition, Vec<u8>) {
+        let did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+        let schema =
+            issuer::create_schema(&did, "schema", "1.0", vec!["name".into()].into(), None).unwrap();
+        let (cred_def, _, _) = issuer::create_credential_definition(
+            &did,
+            &schema,
+            "tag",
+            SignatureType::CL,
+            CredentialDefinitionConfig::new(true),
+        )
+        .unwrap();
+        let mut writer = TailsBufferWriter::new(None);
+        let (rev_reg_def, _, _, _) = issuer::create_revocation_registry(
+            &did,
+            &cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_BY_DEFAULT,
+            max_cred_num,
+            &mut writer,
+        )
+        .unwrap();
+        (rev_reg_def, writer.into_tails().unwrap().0)
+    }
+
+    fn read_tail(accessor: &impl RevocationTailsAccessor, tail_id: u32) -> Option<Vec<u8>> {
+        let mut result = None;
+        accessor
+            .access_tail(tail_id, &mut |tail| {
+                result.replace(tail.to_bytes().unwrap());
+            })
+            .ok()?;
+        result
+    }
+
+    #[test]
+    fn mmap_reader_matches_file_reader() {
+        let (path, _hash) = write_tails_file(5);
+        let file_reader = TailsFileReader::new(&path);
+        let mmap_reader = Arc::new(TailsMmapReader::open(&path).unwrap());
+        assert_eq!(mmap_reader.tails_count(), 11);
+
+        let threads = (0..2)
+            .map(|_| {
+                let reader = mmap_reader.clone();
+                std::thread::spawn(move || {
+                    (0..reader.tails_count())
+                        .map(|idx| read_tail(reader.as_ref(), idx).unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            let tails = thread.join().unwrap();
+            for (idx, tail) in tails.into_iter().enumerate() {
+                assert_eq!(read_tail(&file_reader, idx as u32).unwrap(), tail);
+            }
+        }
+        assert_eq!(read_tail(mmap_reader.as_ref(), 11), None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn buffer_writer_matches_file_writer() {
+        let generator = tails_generator(5);
+        let (path, file_hash) = TailsFileWriter::new(None)
+            .write(&mut generator.clone())
+            .unwrap();
+        let mut buffer_writer = TailsBufferWriter::new(Some("https://tails.example/".into()));
+        let (location, hash) = buffer_writer.write(&mut generator.clone()).unwrap();
+        assert_eq!(hash, file_hash);
+        assert_eq!(location, format!("https://tails.example/{hash}"));
+        assert_eq!(buffer_writer.tails_hash(), Some(hash.as_str()));
+
+        let (tails, _hash) = buffer_writer.into_tails().unwrap();
+        assert_eq!(tails, std::fs::read(&path).unwrap());
+
+        let file_reader = TailsFileReader::new(&path);
+        let buffer_reader = TailsBufferReader::new(tails).unwrap();
+        assert_eq!(buffer_reader.tails_count(), 11);
+        for idx in 0..buffer_reader.tails_count() {
+            assert_eq!(
+                read_tail(&buffer_reader, idx).unwrap(),
+                read_tail(&file_reader, idx).unwrap()
+            );
+        }
+        assert_eq!(read_tail(&buffer_reader, 11), None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn buffer_reader_rejects_invalid_blob() {
+        let mut writer = TailsBufferWriter::new(None);
+        assert!(writer.tails().is_none());
+        writer.write(&mut tails_generator(1)).unwrap();
+        let mut blob = writer.tails().unwrap().to_vec();
+        blob[0] = 1;
+        assert!(TailsBufferReader::new(blob.clone()).is_err());
+        blob[0] = 0;
+        blob.push(0);
+        assert!(TailsBufferReader::new(blob).is_err());
+        assert!(TailsBufferReader::new(vec![0, 2]).is_err());
+    }
+
+    #[test]
+    fn mmap_reader_rejects_invalid_file() {
+        let (path, _hash) = write_tails_file(1);
+        let mut blob = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        blob[1] = 1;
+        std::fs::write(&path, &blob).unwrap();
+        assert!(TailsMmapReader::open(&path).is_err());
+
+        blob[1] = 2;
+        blob.pop();
+        std::fs::write(&path, &blob).unwrap();
+        assert!(TailsMmapReader::open(&path).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn verify_tails_against_registry() {
+        let (rev_reg_def, tails) = registry_with_tails(2);
+        verify_tails(&tails, &rev_reg_def).unwrap();
+        TailsBufferReader::new_verified(tails.clone(), &rev_reg_def).unwrap();
+
+        let path = std::env::temp_dir().join(format!("{:020}.tails", random::<u64>()));
+        std::fs::write(&path, &tails).unwrap();
+        verify_tails_file(&path, &rev_reg_def).unwrap();
+        TailsFileReader::new_verified(&path, &rev_reg_def).unwrap();
+        TailsMmapReader::open_verified(&path, &rev_reg_def).unwrap();
+
+        let mut swapped = tails.clone();
+        let last = swapped.len() - 1;
+        swapped[last] ^= 1;
+        assert_kind!(InvalidTailsFile, verify_tails(&swapped, &rev_reg_def));
+        assert_kind!(
+            InvalidTailsFile,
+            TailsBufferReader::new_verified(swapped.clone(), &rev_reg_def)
+        );
+        std::fs::write(&path, &swapped).unwrap();
+        assert_kind!(InvalidTailsFile, verify_tails_file(&path, &rev_reg_def));
+        assert_kind!(
+            InvalidTailsFile,
+            TailsFileReader::new_verified(&path, &rev_reg_def)
+        );
+        assert_kind!(
+            InvalidTailsFile,
+            TailsMmapReader::open_verified(&path, &rev_reg_def)
+        );
+
+        let mut bad_version = tails.clone();
+        bad_version[1] = 1;
+        assert_kind!(InvalidTailsFile, verify_tails(&bad_version, &rev_reg_def));
+        std::fs::write(&path, &bad_version).unwrap();
+        assert_kind!(InvalidTailsFile, verify_tails_file(&path, &rev_reg_def));
+
+        let truncated = &tails[..tails.len() - TAIL_SIZE];
+        assert_kind!(InvalidTailsFile, verify_tails(truncated, &rev_reg_def));
+        std::fs::write(&path, truncated).unwrap();
+        assert_kind!(InvalidTailsFile, verify_tails_file(&path, &rev_reg_def));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn writer_reports_progress() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let mut writer = TailsBufferWriter::new(None).with_progress({
+            let reported = reported.clone();
+            move |written, total| reported.lock().unwrap().push((written, total))
+        });
+        writer.write(&mut tails_generator(2)).unwrap();
+        assert_eq!(
+            *reported.lock().unwrap(),
+            (0..=5).map(|written| (written, 5)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn progress_reports_are_throttled() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let mut progress = TailsProgress {
+            callback: Some(Box::new({
+                let reported = reported.clone();
+                move |written, _total| reported.lock().unwrap().push(written)
+            })),
+            ..Default::default()
+        };
+        for written in 0..=1001 {
+            progress.report(written, 1001).unwrap();
+        }
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.len(), 102);
+        assert_eq!(reported[..3], [0, 10, 20]);
+        assert_eq!(reported[100..], [1000, 1001]);
+    }
+
+    #[test]
+    fn cancelled_writer_removes_temp_file() {
+        let root_path = std::env::temp_dir().join(format!("tails-{:020}", random::<u64>()));
+        std::fs::create_dir(&root_path).unwrap();
+        let token = CancellationToken::new();
+        let mut writer = TailsFileWriter::new(Some(root_path.to_string_lossy().into_owned()))
+            .with_cancellation(token.clone())
+            .with_progress(move |written, _total| {
+                if written == 2 {
+                    token.cancel();
+                }
+            });
+        assert_kind!(Cancelled, writer.write(&mut tails_generator(5)));
+        assert_eq!(std::fs::read_dir(&root_path).unwrap().count(), 0);
+        std::fs::remove_dir(root_path).unwrap();
+    }
+}
@@ -0,0 +1,66 @@
+use super::helpers::{revocation_registry_delta_parts, RevocationRegistryDeltaParts};
+use super::issuer;
+use super::tails::TailsBufferWriter;
+use super::types::*;
+use crate::anoncreds_clsignatures::Accumulator;
+
+/// A revocation registry with five entries along with its credential definition
+/// and tails
+pub(crate) struct TestRegistry {
+    pub cred_def: CredentialDefinition,
+    pub rev_reg_def: RevocationRegistryDefinition,
+    pub rev_reg_def_private: RevocationRegistryDefinitionPrivate,
+    pub registry: RevocationRegistry,
+    pub initial_delta: RevocationRegistryDelta,
+    pub tails: Vec<u8>,
+}
+
+pub(crate) fn create_registry(issuance_type: IssuanceType) -> TestRegistry {
+    let origin_did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+    let schema = issuer::create_schema(
+        &origin_did,
+        "test",
+        "1.0",
+        vec!["name".to_string()].into(),
+        None,
+    )
+    .unwrap();
+    let (cred_def, _, _) = issuer::create_credential_definition(
+        &origin_did,
+        &schema,
+        "tag",
+        SignatureType::CL,
+        CredentialDefinitionConfig::new(true),
+    )
+    .unwrap();
+    let mut tails_writer = TailsBufferWriter::new(None);
+    let (rev_reg_def, rev_reg_def_private, registry, initial_delta) =
+        issuer::create_revocation_registry(
+            &origin_did,
+            &cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            issuance_type,
+            5,
+            &mut tails_writer,
+        )
+        .unwrap();
+    let (tails, _) = tails_writer.into_tails().unwrap();
+    TestRegistry {
+        cred_def,
+        rev_reg_def,
+        rev_reg_def_private,
+        registry,
+        initial_delta,
+        tails,
+    }
+}
+
+pub(crate) fn parts(delta: &RevocationRegistryDelta) -> RevocationRegistryDeltaParts {
+    revocation_registry_delta_parts(delta).unwrap()
+}
+
+pub(crate) fn accum(registry: &RevocationRegistry) -> Accumulator {
+    let RevocationRegistry::RevocationRegistryV1(registry) = registry;
+    registry.value.accum
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 pub use indy_data_types::{
     anoncreds::{
@@ -10,22 +10,37 @@ pub use indy_data_types::{
         cred_request::{CredentialRequest, CredentialRequestMetadata},
         credential::{AttributeValues, Credential, CredentialValues},
         link_secret::LinkSecret,
-        pres_request::PresentationRequest,
-        presentation::Presentation,
+        nonce::Nonce,
+        pres_request::{
+            AttributeInfo, NonRevocedInterval, PredicateInfo, PredicateTypes, PresentationRequest,
+            PresentationRequestPayload,
+        },
+        presentation::{Identifier, Presentation},
         rev_reg::{RevocationRegistry, RevocationRegistryDelta},
         rev_reg_def::{
             IssuanceType, RegistryType, RevocationRegistryDefinition,
             RevocationRegistryDefinitionPrivate,
         },
         schema::{AttributeNames, Schema},
+        wql::Query,
     },
     did::DidValue,
     invalid, CredentialDefinitionId, RevocationRegistryId, SchemaId, Validatable, ValidationError,
 };
 
+pub use super::credential_selection::{CredentialMatches, CredentialSelection};
+pub use super::pres_request_builder::{
+    PresentationRequestBuilder, RequestedAttribute, RequestedPredicate, Restriction,
+};
+pub use super::revocation_tracker::RevocationRegistryTracker;
+pub use super::verification::{
+    ProofStatus, ReferentReport, SubProofReport, TimestampRule, VerificationFailure,
+    VerificationPolicy, VerificationReport,
+};
+
 use crate::anoncreds_clsignatures::{RevocationRegistry as CryptoRevocationRegistry, Witness};
 use crate::error::Error;
-use crate::services::helpers::encode_credential_attribute;
+use crate::services::helpers::AttributeEncoding;
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct CredentialDefinitionConfig {
@@ -40,42 +55,71 @@ impl CredentialDefinitionConfig {
 
 impl Validatable for CredentialDefinitionConfig {}
 
+/// Credential attribute values together with the encoding used for each
+/// attribute, which the issuer checks before signing.
 #[derive(Debug, Default)]
-pub struct MakeCredentialValues(pub(crate) CredentialValues);
+pub struct MakeCredentialValues {
+    pub(crate) values: CredentialValues,
+    pub(crate) encodings: HashMap<String, AttributeEncoding>,
+}
 
 impl MakeCredentialValues {
+    /// Add a value encoded by the caller. The encoded value must match the
+    /// `Default` encoding of the raw value.
     pub fn add_encoded(
         &mut self,
         name: impl Into<String>,
         raw: impl Into<String>,
         encoded: String,
     ) {
-        self.0 .0.insert(
-            name.into(),
-            AttributeValues {
-                raw: raw.into(),
-                encoded,
-            },
-        );
+        self.insert(name.into(), raw.into(), encoded, AttributeEncoding::Default);
     }
 
     pub fn add_raw(
         &mut self,
         name: impl Into<String>,
         raw: impl Into<String>,
+    ) -> Result<(), Error> {
+        self.add_typed(name, raw, AttributeEncoding::Default)
+    }
+
+    pub fn add_typed(
+        &mut self,
+        name: impl Into<String>,
+        raw: impl Into<String>,
+        encoding: AttributeEncoding,
     ) -> Result<(), Error> {
         let raw = raw.into();
-        let encoded = encode_credential_attribute(&raw)?;
-        self.0
-             .0
-            .insert(name.into(), AttributeValues { raw, encoded });
+        let encoded = encoding.encode(&raw)?;
+        self.insert(name.into(), raw, encoded, encoding);
         Ok(())
     }
+
+    /// The encoding of the attribute `name`, `Default` when it was not
+    /// provided.
+    pub fn encoding(&self, name: &str) -> AttributeEncoding {
+        self.encodings.get(name).copied().unwrap_or_default()
+    }
+
+    fn insert(&mut self, name: String, raw: String, encoded: String, encoding: AttributeEncoding) {
+        self.encodings.insert(name.clone(), encoding);
+        self.values.0.insert(name, AttributeValues { raw, encoded });
+    }
+}
+
+impl From<CredentialValues> for MakeCredentialValues {
+    /// Values built directly use the `Default` encoding for every attribute.
+    fn from(values: CredentialValues) -> MakeCredentialValues {
+        MakeCredentialValues {
+            values,
+            encodings: HashMap::new(),
+        }
+    }
 }
 
 impl From<MakeCredentialValues> for CredentialValues {
     fn from(val: MakeCredentialValues) -> CredentialValues {
-        val.0
+        val.values
     }
 }
 
@@ -178,12 +222,6 @@ impl<'a, 'p> AddCredential<'a, 'p> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub(crate) struct ProvingCredentialKey {
-    pub cred_id: String,
-    pub timestamp: Option<u64>,
-}
-
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CredentialRevocationState {
     pub witness: Witness,
@@ -202,6 +240,27 @@ impl Validatable for CredentialRevocationState {
     }
 }
 
+/// A single credential to be issued by `issuer::create_credential_batch`.
+pub struct CredentialBatchEntry<'a> {
+    pub cred_offer: &'a CredentialOffer,
+    pub cred_request: &'a CredentialRequest,
+    pub cred_values: CredentialValues,
+    pub registry_idx: u32,
+}
+
+impl<'a> std::fmt::Debug for CredentialBatchEntry<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CredentialBatchEntry {{ cred_offer: {:?}, cred_request: {:?}, cred_values: {:?}, idx: {} }}",
+            self.cred_offer,
+            self.cred_request,
+            secret!(&self.cred_values),
+            secret!(self.registry_idx),
+        )
+    }
+}
+
 pub struct CredentialRevocationConfig<'a> {
     pub reg_def: &'a RevocationRegistryDefinition,
     pub reg_def_private: &'a RevocationRegistryDefinitionPrivate,
@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+
+use super::types::*;
+
+/// The rules applied by the verifier in addition to checking the proof.
+///
+/// Deserializing an empty object produces the default policy, which accepts
+/// self-attested attributes and unqualified identifiers, and only checks
+/// non-revocation where the presentation request asks for it. Unknown keys
+/// are rejected, so that a misspelled rule is not silently ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VerificationPolicy {
+    /// Accept revocation proofs which are not linked to the credential, as
+    /// produced by older provers.
+    pub accept_legacy_revocation: bool,
+    pub timestamp_rule: TimestampRule,
+    /// Require every credential issued under a revocable credential
+    /// definition to be proven non-revoked.
+    pub require_non_revocation: bool,
+    /// Reject presentations containing self-attested attributes.
+    pub forbid_self_attested: bool,
+    /// Reject credentials whose schema, credential definition or revocation
+    /// registry identifiers are not fully qualified.
+    pub require_qualified_identifiers: bool,
+}
+
+impl VerificationPolicy {
+    /// The policy of `verifier::verify_presentation`, which only requires a
+    /// timestamp to be present where the request specifies a non-revocation
+    /// interval. This is also the `Default` policy.
+    pub fn basic() -> Self {
+        Self::default()
+    }
+
+    /// The basic policy, accepting presentations with unlinked revocation
+    /// proofs.
+    pub fn legacy() -> Self {
+        Self {
+            accept_legacy_revocation: true,
+            ..Self::basic()
+        }
+    }
+}
+
+/// The check applied to the timestamp of a sub-proof when the request
+/// specifies a non-revocation interval for a referent.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampRule {
+    /// A timestamp must be provided, but neither its value nor the registry
+    /// state in effect at that time is compared with the interval.
+    #[default]
+    Present,
+    /// The timestamp must not be later than the end of the interval. An
+    /// earlier timestamp is accepted when no registry update was published
+    /// within the interval.
+    NotAfterInterval,
+    /// The timestamp must fall within the interval.
+    WithinInterval,
+}
+
+/// The outcome of `verifier::verify_presentation_report`.
+///
+/// The presentation is valid only when the proof was verified and no failures
+/// were reported for any referent or sub-proof.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    /// The outcome for each requested attribute and predicate referent.
+    pub referents: BTreeMap<String, ReferentReport>,
+    /// The outcome for each sub-proof, in the order of the presentation identifiers.
+    pub sub_proofs: Vec<SubProofReport>,
+    pub proof: ProofStatus,
+}
+
+impl VerificationReport {
+    pub fn is_valid(&self) -> bool {
+        self.proof == ProofStatus::Verified && self.failures().next().is_none()
+    }
+
+    /// All failures reported for referents and sub-proofs.
+    pub fn failures(&self) -> impl Iterator<Item = &VerificationFailure> {
+        self.referents
+            .values()
+            .flat_map(|referent| referent.failures.iter())
+            .chain(
+                self.sub_proofs
+                    .iter()
+                    .flat_map(|sub_proof| sub_proof.failures.iter()),
+            )
+    }
+}
+
+/// The outcome for a single referent of a presentation request. Self-attested
+/// attributes have no sub-proof index.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferentReport {
+    pub sub_proof_index: Option<u32>,
+    pub failures: Vec<VerificationFailure>,
+}
+
+/// The outcome for a single sub-proof (credential) of a presentation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubProofReport {
+    pub identifier: Identifier,
+    pub failures: Vec<VerificationFailure>,
+}
+
+/// The outcome of verifying the cryptographic proof.
+///
+/// The sub-proofs, including any non-revocation proofs, are verified together.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofStatus {
+    Verified,
+    Rejected,
+    /// The proof could not be checked because a sub-proof failed to resolve
+    /// its revocation registry.
+    NotChecked,
+}
+
+/// A reason for rejecting a presentation, with the identifiers involved.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VerificationFailure {
+    /// The credential does not satisfy the restrictions of the referent.
+    RestrictionMismatch {
+        schema_id: SchemaId,
+        cred_def_id: CredentialDefinitionId,
+        reason: String,
+    },
+    /// The non-revocation timestamp is missing or not accepted for the
+    /// requested interval under the `TimestampRule` of the policy.
+    TimestampOutOfInterval {
+        rev_reg_id: Option<RevocationRegistryId>,
+        timestamp: Option<u64>,
+        interval: NonRevocedInterval,
+    },
+    /// The revealed value does not correspond to the value in the proof.
+    RevealedValueMismatch {
+        attr_name: String,
+        sub_proof_index: u32,
+        reason: String,
+    },
+    /// No state of the revocation registry was provided at or before the
+    /// sub-proof timestamp.
+    MissingRegistry {
+        rev_reg_id: RevocationRegistryId,
+        timestamp: u64,
+    },
+    /// The registry state in effect at the sub-proof timestamp was published
+    /// after the end of the requested interval, or replaced before it began.
+    RegistryStateOutOfInterval {
+        rev_reg_id: RevocationRegistryId,
+        timestamp: u64,
+        published: u64,
+        replaced: Option<u64>,
+        interval: NonRevocedInterval,
+    },
+    /// The proof was rejected and the sub-proof proves non-revocation against
+    /// the registry state in effect at `timestamp`. A credential revoked in
+    /// that state cannot be told from a witness which was not updated to it
+    /// or from an otherwise invalid proof.
+    RevocationProofRejected {
+        rev_reg_id: RevocationRegistryId,
+        timestamp: u64,
+    },
+    /// The credential definition supports revocation but the sub-proof does
+    /// not prove non-revocation.
+    MissingNonRevocationProof { cred_def_id: CredentialDefinitionId },
+    /// The attribute was self-attested but the policy forbids it.
+    SelfAttestedAttribute,
+    /// An identifier of the sub-proof is not fully qualified.
+    UnqualifiedIdentifier { id: String },
+}
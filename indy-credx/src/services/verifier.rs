@@ -1,19 +1,28 @@
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use regex::Regex;
 
 use super::helpers::*;
+use super::resolver::{LedgerResolver, MapResolver, RegistryState, Resolved};
 use super::types::*;
-use crate::anoncreds_clsignatures::{CredentialPublicKey, Verifier as ClVerifier};
-use crate::error::Result;
+use crate::anoncreds_clsignatures::{
+    CredentialPublicKey, CredentialSchema, ErrorKind as ClErrorKind, NonCredentialSchema,
+    Nonce as ClNonce, SubProofRequest, Verifier as ClVerifier,
+};
+use crate::error::{Error, ErrorKind, Result};
 use indy_data_types::anoncreds::{
+    cred_def::CredentialDefinitionV1,
     nonce::Nonce,
     pres_request::{AttributeInfo, NonRevocedInterval, PredicateInfo, PresentationRequestPayload},
     presentation::{Identifier, RequestedProof, RevealedAttributeInfo},
-    wql::Query,
+    schema::SchemaV1,
+    wql::{compare_values, like_matches, Query},
 };
+use indy_data_types::qualifiable::is_fully_qualified;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Filter {
@@ -28,6 +37,12 @@ pub struct Filter {
 static INTERNAL_TAG_MATCHER: Lazy<Regex> =
     Lazy::new(|| Regex::new("^attr::([^:]+)::(value|marker)$").unwrap());
 
+/// Verify a presentation under `VerificationPolicy::basic`, returning `false`
+/// when the proof is rejected.
+///
+/// A presentation which does not satisfy the restrictions of the request, or
+/// references a revocation registry state which was not provided, produces an
+/// error. Use `verify_presentation_report` to collect these outcomes instead.
 pub fn verify_presentation(
     presentation: &Presentation,
     pres_req: &PresentationRequest,
@@ -43,11 +58,15 @@ pub fn verify_presentation(
         cred_defs,
         rev_reg_defs,
         rev_regs,
-        false,
+        &VerificationPolicy::basic(),
     )
 }
 
-/// Temporary method for verifying presentations with unlinked revocation proofs
+/// Verify a presentation, accepting unlinked revocation proofs.
+#[deprecated(
+    since = "1.1.1",
+    note = "use `verify_presentation_with_policy` with `VerificationPolicy::legacy()`"
+)]
 pub fn verify_presentation_legacy(
     presentation: &Presentation,
     pres_req: &PresentationRequest,
@@ -63,10 +82,177 @@ pub fn verify_presentation_legacy(
         cred_defs,
         rev_reg_defs,
         rev_regs,
-        true,
+        &VerificationPolicy::legacy(),
+    )
+}
+
+/// Verify a presentation, applying the rules of a verification policy.
+///
+/// Presentations with unlinked revocation proofs are accepted under
+/// `VerificationPolicy::legacy`. As with `verify_presentation`, a presentation
+/// which does not satisfy the request produces an error, while `false` is
+/// returned when the proof or the rules of the policy reject it.
+pub fn verify_presentation_with_policy(
+    presentation: &Presentation,
+    pres_req: &PresentationRequest,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+    rev_reg_defs: Option<&HashMap<RevocationRegistryId, &RevocationRegistryDefinition>>,
+    rev_regs: Option<&HashMap<RevocationRegistryId, HashMap<u64, &RevocationRegistry>>>,
+    policy: &VerificationPolicy,
+) -> Result<bool> {
+    _verify_presentation(
+        presentation,
+        pres_req,
+        schemas,
+        cred_defs,
+        rev_reg_defs,
+        rev_regs,
+        policy,
+    )
+}
+
+/// Verify a presentation, reporting the outcome for each referent and sub-proof.
+///
+/// Presentations which do not correspond to the request, or which reference
+/// schemas and credential definitions that were not provided, produce an error.
+pub fn verify_presentation_report(
+    presentation: &Presentation,
+    pres_req: &PresentationRequest,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+    rev_reg_defs: Option<&HashMap<RevocationRegistryId, &RevocationRegistryDefinition>>,
+    rev_regs: Option<&HashMap<RevocationRegistryId, HashMap<u64, &RevocationRegistry>>>,
+    policy: &VerificationPolicy,
+) -> Result<VerificationReport> {
+    let resolver = MapResolver {
+        schemas,
+        cred_defs,
+        rev_reg_defs,
+        rev_regs,
+    };
+    _verify_presentation_report(
+        presentation,
+        pres_req,
+        &resolver,
+        &VerifierCache::default(),
+        policy,
     )
 }
 
+/// Verify a presentation under `VerificationPolicy::legacy`, reporting the
+/// outcome for each referent and sub-proof.
+#[deprecated(
+    since = "1.1.1",
+    note = "use `verify_presentation_report` with `VerificationPolicy::legacy()`"
+)]
+pub fn verify_presentation_report_legacy(
+    presentation: &Presentation,
+    pres_req: &PresentationRequest,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+    rev_reg_defs: Option<&HashMap<RevocationRegistryId, &RevocationRegistryDefinition>>,
+    rev_regs: Option<&HashMap<RevocationRegistryId, HashMap<u64, &RevocationRegistry>>>,
+) -> Result<VerificationReport> {
+    verify_presentation_report(
+        presentation,
+        pres_req,
+        schemas,
+        cred_defs,
+        rev_reg_defs,
+        rev_regs,
+        &VerificationPolicy::legacy(),
+    )
+}
+
+/// Verify a presentation, looking up the referenced ledger objects as they
+/// are needed.
+pub fn verify_presentation_report_with_resolver(
+    presentation: &Presentation,
+    pres_req: &PresentationRequest,
+    resolver: &impl LedgerResolver,
+    policy: &VerificationPolicy,
+) -> Result<VerificationReport> {
+    _verify_presentation_report(
+        presentation,
+        pres_req,
+        resolver,
+        &VerifierCache::default(),
+        policy,
+    )
+}
+
+/// Verify a batch of presentations on a thread pool, returning a result for
+/// each presentation in order.
+///
+/// The ledger objects are resolved through the shared resolver, and the
+/// structures derived from schemas and credential definitions are built once
+/// for the whole batch.
+#[cfg(feature = "parallel")]
+pub fn verify_presentations_batch<R>(
+    presentations: &[(&Presentation, &PresentationRequest)],
+    resolver: &R,
+    policy: &VerificationPolicy,
+) -> Vec<Result<VerificationReport>>
+where
+    R: LedgerResolver + Sync,
+{
+    use rayon::prelude::*;
+
+    let cache = VerifierCache::default();
+    presentations
+        .par_iter()
+        .map(|(presentation, pres_req)| {
+            _verify_presentation_report(presentation, pres_req, resolver, &cache, policy)
+        })
+        .collect()
+}
+
+/// Cryptographic structures derived from ledger objects, which are shared
+/// between the verifications of a batch.
+#[derive(Default)]
+struct VerifierCache {
+    non_credential_schema: OnceCell<NonCredentialSchema>,
+    credential_schemas: Mutex<HashMap<SchemaId, Arc<CredentialSchema>>>,
+    credential_pub_keys: Mutex<HashMap<CredentialDefinitionId, Arc<CredentialPublicKey>>>,
+}
+
+impl VerifierCache {
+    fn non_credential_schema(&self) -> Result<&NonCredentialSchema> {
+        self.non_credential_schema
+            .get_or_try_init(build_non_credential_schema)
+    }
+
+    fn credential_schema(
+        &self,
+        schema_id: &SchemaId,
+        schema: &SchemaV1,
+    ) -> Result<Arc<CredentialSchema>> {
+        if let Some(cached) = lock(&self.credential_schemas).get(schema_id) {
+            return Ok(cached.clone());
+        }
+        let credential_schema = Arc::new(build_credential_schema(&schema.attr_names.0)?);
+        lock(&self.credential_schemas).insert(schema_id.clone(), credential_schema.clone());
+        Ok(credential_schema)
+    }
+
+    fn credential_pub_key(
+        &self,
+        cred_def_id: &CredentialDefinitionId,
+        cred_def: &CredentialDefinitionV1,
+    ) -> Result<Arc<CredentialPublicKey>> {
+        if let Some(cached) = lock(&self.credential_pub_keys).get(cred_def_id) {
+            return Ok(cached.clone());
+        }
+        let credential_pub_key = Arc::new(CredentialPublicKey::build_from_parts(
+            &cred_def.value.primary,
+            cred_def.value.revocation.as_ref(),
+        )?);
+        lock(&self.credential_pub_keys).insert(cred_def_id.clone(), credential_pub_key.clone());
+        Ok(credential_pub_key)
+    }
+}
+
 pub(crate) fn _verify_presentation(
     presentation: &Presentation,
     pres_req: &PresentationRequest,
@@ -74,10 +260,93 @@ pub(crate) fn _verify_presentation(
     cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
     rev_reg_defs: Option<&HashMap<RevocationRegistryId, &RevocationRegistryDefinition>>,
     rev_regs: Option<&HashMap<RevocationRegistryId, HashMap<u64, &RevocationRegistry>>>,
-    accept_legacy_revocation: bool,
+    policy: &VerificationPolicy,
 ) -> Result<bool> {
-    trace!("verify >>> presentation: {:?}, pres_req: {:?}, schemas: {:?}, cred_defs: {:?}, rev_reg_defs: {:?} rev_regs: {:?}",
-    presentation, pres_req, schemas, cred_defs, rev_reg_defs, rev_regs);
+    let resolver = MapResolver {
+        schemas,
+        cred_defs,
+        rev_reg_defs,
+        rev_regs,
+    };
+    let report = _verify_presentation_report(
+        presentation,
+        pres_req,
+        &resolver,
+        &VerifierCache::default(),
+        policy,
+    )?;
+    if !report.is_valid() {
+        debug!("Presentation rejected: {:?}", report);
+    }
+    if let Some((location, failure)) = first_failure(&report) {
+        // revealed values which do not match the proof were always rejected
+        let kind = match failure {
+            VerificationFailure::RevealedValueMismatch { .. } => ErrorKind::ProofRejected,
+            _ => ErrorKind::Input,
+        };
+        return Err(Error::from_msg(
+            kind,
+            format!(
+                "Presentation verification failed for {}: {:?}",
+                location, failure
+            ),
+        ));
+    }
+    Ok(report.proof == ProofStatus::Verified)
+}
+
+/// Find the first failure of a report along with the referent or sub-proof
+/// it was reported for. A rejected non-revocation proof and the failures
+/// introduced by a `VerificationPolicy` are reported as a rejected proof.
+fn first_failure(report: &VerificationReport) -> Option<(String, &VerificationFailure)> {
+    let referents = report.referents.iter().flat_map(|(referent, report)| {
+        report
+            .failures
+            .iter()
+            .map(move |failure| (format!("referent {:?}", referent), failure))
+    });
+    let sub_proofs = report
+        .sub_proofs
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, report)| {
+            report
+                .failures
+                .iter()
+                .map(move |failure| (format!("sub-proof {}", idx), failure))
+        });
+    referents
+        .chain(sub_proofs)
+        .find(|(_, failure)| !is_rejection(failure))
+}
+
+fn is_rejection(failure: &VerificationFailure) -> bool {
+    matches!(
+        failure,
+        VerificationFailure::RevocationProofRejected { .. }
+            | VerificationFailure::TimestampOutOfInterval {
+                timestamp: Some(_),
+                ..
+            }
+            | VerificationFailure::RegistryStateOutOfInterval { .. }
+            | VerificationFailure::MissingNonRevocationProof { .. }
+            | VerificationFailure::SelfAttestedAttribute
+            | VerificationFailure::UnqualifiedIdentifier { .. }
+    )
+}
+
+fn _verify_presentation_report(
+    presentation: &Presentation,
+    pres_req: &PresentationRequest,
+    resolver: &dyn LedgerResolver,
+    cache: &VerifierCache,
+    policy: &VerificationPolicy,
+) -> Result<VerificationReport> {
+    trace!(
+        "verify >>> presentation: {:?}, pres_req: {:?}",
+        presentation,
+        pres_req
+    );
 
     let pres_req = pres_req.value();
     let received_revealed_attrs: HashMap<String, Identifier> =
@@ -95,7 +364,9 @@ pub(crate) fn _verify_presentation(
         &received_predicates,
     )?;
 
-    verify_revealed_attribute_values(pres_req, presentation)?;
+    let mut report = new_report(presentation);
+
+    verify_revealed_attribute_values(pres_req, presentation, &mut report)?;
 
     verify_requested_restrictions(
         pres_req,
@@ -104,77 +375,103 @@ pub(crate) fn _verify_presentation(
         &received_unrevealed_attrs,
         &received_predicates,
         &received_self_attested_attrs,
+        &mut report,
     )?;
 
     compare_timestamps_from_proof_and_request(
         pres_req,
         &received_revealed_attrs,
         &received_unrevealed_attrs,
-        &received_self_attested_attrs,
         &received_predicates,
-    )?;
+        policy.timestamp_rule,
+        &mut report,
+    );
 
-    let mut proof_verifier = ClVerifier::new_proof_verifier()?;
-    proof_verifier.accept_legacy_revocation(accept_legacy_revocation);
-    let non_credential_schema = build_non_credential_schema()?;
+    if policy.forbid_self_attested {
+        for referent in received_self_attested_attrs.iter() {
+            add_referent_failure(
+                &mut report,
+                referent,
+                VerificationFailure::SelfAttestedAttribute,
+            );
+        }
+    }
+
+    if policy.require_qualified_identifiers {
+        verify_qualified_identifiers(presentation, &mut report);
+    }
+
+    let non_credential_schema = cache.non_credential_schema()?;
+    let mut sub_proofs = Vec::with_capacity(presentation.identifiers.len());
+    let mut registries_resolved = true;
 
     for sub_proof_index in 0..presentation.identifiers.len() {
         let identifier = presentation.identifiers[sub_proof_index].clone();
 
-        let Schema::SchemaV1(schema) = schemas
-            .get(&identifier.schema_id)
+        let schema = resolver
+            .resolve_schema(&identifier.schema_id)?
             .ok_or_else(|| err_msg!("Schema not provided for ID: {:?}", identifier.schema_id))?;
+        let Schema::SchemaV1(schema) = &*schema;
 
-        let CredentialDefinition::CredentialDefinitionV1(cred_def) =
-            cred_defs.get(&identifier.cred_def_id).ok_or_else(|| {
+        let cred_def = resolver
+            .resolve_cred_def(&identifier.cred_def_id)?
+            .ok_or_else(|| {
                 err_msg!(
                     "Credential Definition not provided for ID: {:?}",
                     identifier.cred_def_id
                 )
             })?;
+        let CredentialDefinition::CredentialDefinitionV1(cred_def) = &*cred_def;
+
+        if policy.require_non_revocation
+            && cred_def.value.revocation.is_some()
+            && identifier.timestamp.is_none()
+        {
+            report.sub_proofs[sub_proof_index].failures.push(
+                VerificationFailure::MissingNonRevocationProof {
+                    cred_def_id: identifier.cred_def_id.clone(),
+                },
+            );
+        }
 
-        let (rev_reg_def, rev_reg) = if let Some(timestamp) = identifier.timestamp {
+        let revocation = if let Some(timestamp) = identifier.timestamp {
             let rev_reg_id = identifier.rev_reg_id.clone().ok_or_else(|| {
                 err_msg!("Timestamp provided but Revocation Registry Id not found")
             })?;
-            if rev_reg_defs.is_none() {
-                return Err(err_msg!(
-                    "Timestamp provided but no Revocation Registry Definitions found"
-                ));
-            }
-            if rev_regs.is_none() {
-                return Err(err_msg!(
-                    "Timestamp provided but no Revocation Registries found"
-                ));
-            }
-
-            let rev_reg_def = Some(rev_reg_defs.as_ref().unwrap().get(&rev_reg_id).ok_or_else(
-                || {
-                    err_msg!(
-                        "Revocation Registry Definition not provided for ID: {:?}",
-                        rev_reg_id
-                    )
-                },
-            )?);
-
-            let rev_reg = Some(
-                rev_regs
-                    .as_ref()
-                    .unwrap()
-                    .get(&rev_reg_id)
-                    .and_then(|regs| regs.get(&timestamp))
-                    .ok_or_else(|| {
-                        err_msg!(
-                            "Revocation Registry not provided for ID and timestamp: {:?}, {:?}",
+            let rev_reg_def = resolver.resolve_rev_reg_def(&rev_reg_id)?;
+            let rev_reg = resolver.resolve_rev_reg_at(&rev_reg_id, timestamp)?;
+            match (rev_reg_def, rev_reg) {
+                (Some(rev_reg_def), Some(state)) => {
+                    if policy.timestamp_rule != TimestampRule::Present {
+                        compare_registry_state_with_request(
+                            pres_req,
+                            sub_proof_index as u32,
+                            &rev_reg_id,
+                            timestamp,
+                            &state,
+                            &mut report,
+                        );
+                    }
+                    Some(SubProofRevocation {
+                        rev_reg_id,
+                        timestamp,
+                        rev_reg_def,
+                        state,
+                    })
+                }
+                _ => {
+                    report.sub_proofs[sub_proof_index].failures.push(
+                        VerificationFailure::MissingRegistry {
                             rev_reg_id,
-                            timestamp
-                        )
-                    })?,
-            );
-
-            (rev_reg_def, rev_reg)
+                            timestamp,
+                        },
+                    );
+                    registries_resolved = false;
+                    continue;
+                }
+            }
         } else {
-            (None, None)
+            None
         };
 
         let attrs_for_credential = get_revealed_attributes_for_credential(
@@ -188,39 +485,159 @@ pub(crate) fn _verify_presentation(
             pres_req,
         )?;
 
-        let credential_schema = build_credential_schema(&schema.attr_names.0)?;
-        let sub_pres_request =
-            build_sub_proof_request(&attrs_for_credential, &predicates_for_credential)?;
+        sub_proofs.push(SubProofInput {
+            sub_proof_request: build_sub_proof_request(
+                &attrs_for_credential,
+                &predicates_for_credential,
+            )?,
+            credential_schema: cache.credential_schema(&identifier.schema_id, schema)?,
+            credential_pub_key: cache.credential_pub_key(&identifier.cred_def_id, cred_def)?,
+            revocation,
+        });
+    }
 
-        let credential_pub_key = CredentialPublicKey::build_from_parts(
-            &cred_def.value.primary,
-            cred_def.value.revocation.as_ref(),
+    if registries_resolved {
+        report.proof = verify_sub_proofs(
+            presentation,
+            pres_req.nonce.as_native(),
+            &sub_proofs,
+            non_credential_schema,
+            policy.accept_legacy_revocation,
         )?;
 
-        let rev_key_pub = rev_reg_def.as_ref().map(|r_reg_def| match r_reg_def {
-            RevocationRegistryDefinition::RevocationRegistryDefinitionV1(reg_def) => {
-                &reg_def.value.public_keys.accum_key
+        // the proof does not show which sub-proof failed, so the registry
+        // state of each non-revocation proof is reported for the caller
+        if report.proof == ProofStatus::Rejected {
+            for (sub_proof_index, sub_proof) in sub_proofs.iter().enumerate() {
+                if let Some(revocation) = &sub_proof.revocation {
+                    report.sub_proofs[sub_proof_index].failures.push(
+                        VerificationFailure::RevocationProofRejected {
+                            rev_reg_id: revocation.rev_reg_id.clone(),
+                            timestamp: revocation.timestamp,
+                        },
+                    );
+                }
             }
-        });
-        let rev_reg = rev_reg.as_ref().map(|r_reg| match r_reg {
-            RevocationRegistry::RevocationRegistryV1(reg_def) => &reg_def.value,
-        });
+        }
+    }
 
+    trace!("verify <<< report: {:?}", report);
+
+    Ok(report)
+}
+
+/// The inputs for verifying a sub-proof.
+struct SubProofInput<'r> {
+    sub_proof_request: SubProofRequest,
+    credential_schema: Arc<CredentialSchema>,
+    credential_pub_key: Arc<CredentialPublicKey>,
+    revocation: Option<SubProofRevocation<'r>>,
+}
+
+struct SubProofRevocation<'r> {
+    rev_reg_id: RevocationRegistryId,
+    timestamp: u64,
+    rev_reg_def: Resolved<'r, RevocationRegistryDefinition>,
+    state: RegistryState<'r>,
+}
+
+/// Verify the cryptographic proof of a presentation.
+fn verify_sub_proofs(
+    presentation: &Presentation,
+    nonce: &ClNonce,
+    sub_proofs: &[SubProofInput<'_>],
+    non_credential_schema: &NonCredentialSchema,
+    accept_legacy_revocation: bool,
+) -> Result<ProofStatus> {
+    let mut proof_verifier = ClVerifier::new_proof_verifier()?;
+    proof_verifier.accept_legacy_revocation(accept_legacy_revocation);
+
+    for sub_proof in sub_proofs {
+        let (rev_key_pub, rev_reg) = match &sub_proof.revocation {
+            Some(revocation) => {
+                let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) =
+                    &*revocation.rev_reg_def;
+                let RevocationRegistry::RevocationRegistryV1(rev_reg) = &*revocation.state.rev_reg;
+                (
+                    Some(&rev_reg_def.value.public_keys.accum_key),
+                    Some(&rev_reg.value),
+                )
+            }
+            None => (None, None),
+        };
         proof_verifier.add_sub_proof_request(
-            &sub_pres_request,
-            &credential_schema,
-            &non_credential_schema,
-            &credential_pub_key,
+            &sub_proof.sub_proof_request,
+            &sub_proof.credential_schema,
+            non_credential_schema,
+            &sub_proof.credential_pub_key,
             rev_key_pub,
             rev_reg,
         )?;
     }
 
-    let valid = proof_verifier.verify(&presentation.proof, pres_req.nonce.as_native())?;
+    match proof_verifier.verify(&presentation.proof, nonce) {
+        Ok(true) => Ok(ProofStatus::Verified),
+        Ok(false) => Ok(ProofStatus::Rejected),
+        Err(err) if err.kind() == ClErrorKind::ProofRejected => Ok(ProofStatus::Rejected),
+        Err(err) => Err(err.into()),
+    }
+}
 
-    trace!("verify <<< valid: {:?}", valid);
+fn new_report(presentation: &Presentation) -> VerificationReport {
+    let requested_proof = &presentation.requested_proof;
+    let mut referents = BTreeMap::new();
+    for (referent, info) in requested_proof.revealed_attrs.iter() {
+        referents.insert(referent.clone(), Some(info.sub_proof_index));
+    }
+    for (referent, info) in requested_proof.revealed_attr_groups.iter() {
+        referents.insert(referent.clone(), Some(info.sub_proof_index));
+    }
+    for (referent, info) in requested_proof.unrevealed_attrs.iter() {
+        referents.insert(referent.clone(), Some(info.sub_proof_index));
+    }
+    for referent in requested_proof.self_attested_attrs.keys() {
+        referents.insert(referent.clone(), None);
+    }
+    for (referent, info) in requested_proof.predicates.iter() {
+        referents.insert(referent.clone(), Some(info.sub_proof_index));
+    }
 
-    Ok(valid)
+    VerificationReport {
+        referents: referents
+            .into_iter()
+            .map(|(referent, sub_proof_index)| {
+                (
+                    referent,
+                    ReferentReport {
+                        sub_proof_index,
+                        failures: Vec::new(),
+                    },
+                )
+            })
+            .collect(),
+        sub_proofs: presentation
+            .identifiers
+            .iter()
+            .map(|identifier| SubProofReport {
+                identifier: identifier.clone(),
+                failures: Vec::new(),
+            })
+            .collect(),
+        proof: ProofStatus::NotChecked,
+    }
+}
+
+fn add_referent_failure(
+    report: &mut VerificationReport,
+    referent: &str,
+    failure: VerificationFailure,
+) {
+    report
+        .referents
+        .entry(referent.to_string())
+        .or_default()
+        .failures
+        .push(failure);
 }
 
 pub fn generate_nonce() -> Result<Nonce> {
@@ -338,50 +755,109 @@ fn compare_timestamps_from_proof_and_request(
     pres_req: &PresentationRequestPayload,
     received_revealed_attrs: &HashMap<String, Identifier>,
     received_unrevealed_attrs: &HashMap<String, Identifier>,
-    received_self_attested_attrs: &HashSet<String>,
     received_predicates: &HashMap<String, Identifier>,
-) -> Result<()> {
-    pres_req
+    rule: TimestampRule,
+    report: &mut VerificationReport,
+) {
+    let attrs = pres_req
         .requested_attributes
         .iter()
-        .map(|(referent, info)| {
-            validate_timestamp(
-                received_revealed_attrs,
-                referent,
-                &pres_req.non_revoked,
-                &info.non_revoked,
-            )
-            .or_else(|_| {
-                validate_timestamp(
-                    received_unrevealed_attrs,
-                    referent,
-                    &pres_req.non_revoked,
-                    &info.non_revoked,
-                )
-            })
-            .or_else(|_| {
-                received_self_attested_attrs
-                    .get(referent)
-                    .map(|_| ())
-                    .ok_or_else(|| err_msg!("Missing referent"))
-            })
-        })
-        .collect::<Result<Vec<()>>>()?;
-
-    pres_req
+        .filter_map(|(referent, info)| {
+            let received = if received_revealed_attrs.contains_key(referent) {
+                received_revealed_attrs
+            } else if received_unrevealed_attrs.contains_key(referent) {
+                received_unrevealed_attrs
+            } else {
+                // self-attested
+                return None;
+            };
+            Some((referent, &info.non_revoked, received))
+        });
+    let predicates = pres_req
         .requested_predicates
         .iter()
-        .map(|(referent, info)| {
-            validate_timestamp(
-                received_predicates,
+        .map(|(referent, info)| (referent, &info.non_revoked, received_predicates));
+
+    for (referent, local_interval, received) in attrs.chain(predicates) {
+        if let Err(err) = validate_timestamp(
+            received,
+            referent,
+            &pres_req.non_revoked,
+            local_interval,
+            rule,
+        ) {
+            debug!("Timestamp validation failed for \"{}\": {}", referent, err);
+            let interval = get_non_revoc_interval(&pres_req.non_revoked, local_interval).unwrap_or(
+                NonRevocedInterval {
+                    from: None,
+                    to: None,
+                },
+            );
+            let identifier = received.get(referent);
+            add_referent_failure(
+                report,
                 referent,
-                &pres_req.non_revoked,
-                &info.non_revoked,
-            )
+                VerificationFailure::TimestampOutOfInterval {
+                    rev_reg_id: identifier.and_then(|id| id.rev_reg_id.clone()),
+                    timestamp: identifier.and_then(|id| id.timestamp),
+                    interval,
+                },
+            );
+        }
+    }
+}
+
+/// Check that the registry state used for a sub-proof was in effect at some
+/// time within the interval of each referent it satisfies. Without a lower
+/// bound, the state must still be in effect at the end of the interval.
+fn compare_registry_state_with_request(
+    pres_req: &PresentationRequestPayload,
+    sub_proof_index: u32,
+    rev_reg_id: &RevocationRegistryId,
+    timestamp: u64,
+    state: &RegistryState<'_>,
+    report: &mut VerificationReport,
+) {
+    let referents: Vec<(String, NonRevocedInterval)> = report
+        .referents
+        .iter()
+        .filter(|(_, referent)| referent.sub_proof_index == Some(sub_proof_index))
+        .filter_map(|(referent, _)| {
+            let local_interval = pres_req
+                .requested_attributes
+                .get(referent)
+                .map(|info| &info.non_revoked)
+                .or_else(|| {
+                    pres_req
+                        .requested_predicates
+                        .get(referent)
+                        .map(|info| &info.non_revoked)
+                })?;
+            let interval = get_non_revoc_interval(&pres_req.non_revoked, local_interval)?;
+            Some((referent.clone(), interval))
         })
-        .collect::<Result<Vec<()>>>()?;
+        .collect();
 
-    Ok(())
+    for (referent, interval) in referents {
+        let published_after = interval.to.is_some_and(|to| state.published > to);
+        let replaced_before = match (state.replaced, interval.from.or(interval.to)) {
+            (Some(replaced), Some(start)) => replaced <= start,
+            _ => false,
+        };
+        if published_after || replaced_before {
+            add_referent_failure(
+                report,
+                &referent,
+                VerificationFailure::RegistryStateOutOfInterval {
+                    rev_reg_id: rev_reg_id.clone(),
+                    timestamp,
+                    published: state.published,
+                    replaced: state.replaced,
+                    interval,
+                },
+            );
+        }
+    }
 }
 
 fn validate_timestamp(
@@ -389,17 +865,39 @@ fn validate_timestamp(
     referent: &str,
     global_interval: &Option<NonRevocedInterval>,
     local_interval: &Option<NonRevocedInterval>,
+    rule: TimestampRule,
 ) -> Result<()> {
-    if get_non_revoc_interval(global_interval, local_interval).is_none() {
+    let Some(interval) = get_non_revoc_interval(global_interval, local_interval) else {
         return Ok(());
-    }
+    };
 
-    if !received_
+    let timestamp = received_
         .get(referent)
-        .map(|attr| attr.timestamp.is_some())
-        .unwrap_or(false)
-    {
-        return Err(err_msg!("Missing timestamp"));
+        .and_then(|attr| attr.timestamp)
+        .ok_or_else(|| err_msg!("Missing timestamp"))?;
+
+    // a credential must not be proven non-revoked at a time after the interval,
+    // but the default rule lets the prover use an earlier registry state when
+    // none was published within the interval
+    let after = interval.to.is_some_and(|to| timestamp > to);
+    let before = interval.from.is_some_and(|from| timestamp < from);
+    match rule {
+        TimestampRule::Present => {}
+        TimestampRule::NotAfterInterval if after => {
+            return Err(err_msg!(
+                "Timestamp {} is later than the end of the interval {:?}",
+                timestamp,
+                interval
+            ));
+        }
+        TimestampRule::WithinInterval if after || before => {
+            return Err(err_msg!(
+                "Timestamp {} is outside of the interval {:?}",
+                timestamp,
+                interval
+            ));
+        }
+        _ => {}
     }
 
     Ok(())
@@ -461,9 +959,31 @@ fn get_proof_identifier(proof: &Presentation, index: u32) -> Result<Identifier>
         .ok_or_else(|| err_msg!("Identifier not found for index: {}", index))
 }
 
+fn verify_qualified_identifiers(presentation: &Presentation, report: &mut VerificationReport) {
+    for (identifier, sub_proof) in presentation
+        .identifiers
+        .iter()
+        .zip(report.sub_proofs.iter_mut())
+    {
+        let ids = [
+            Some(&identifier.schema_id.0),
+            Some(&identifier.cred_def_id.0),
+            identifier.rev_reg_id.as_ref().map(|id| &id.0),
+        ];
+        for id in ids.into_iter().flatten() {
+            if !is_fully_qualified(id) {
+                sub_proof
+                    .failures
+                    .push(VerificationFailure::UnqualifiedIdentifier { id: id.clone() });
+            }
+        }
+    }
+}
+
 fn verify_revealed_attribute_values(
     pres_req: &PresentationRequestPayload,
     proof: &Presentation,
+    report: &mut VerificationReport,
 ) -> Result<()> {
     for (attr_referent, attr_info) in proof.requested_proof.revealed_attrs.iter() {
         let attr_name = pres_req
@@ -486,7 +1006,9 @@ fn verify_revealed_attribute_values(
                     attr_referent,
                 )
             })?;
-        verify_revealed_attribute_value(attr_name.as_str(), proof, attr_info)?;
+        if let Err(err) = verify_revealed_attribute_value(attr_name.as_str(), proof, attr_info) {
+            add_revealed_value_failure(report, attr_referent, attr_name, attr_info, err);
+        }
     }
 
     for (attr_referent, attr_infos) in proof.requested_proof.revealed_attr_groups.iter() {
@@ -520,20 +1042,37 @@ fn verify_revealed_attribute_values(
             let attr_info = &attr_infos.values.get(attr_name).ok_or_else(|| {
                 err_msg!("Proof Revealed Attr Group does not match Proof Request Attribute Group",)
             })?;
-            verify_revealed_attribute_value(
-                attr_name,
-                proof,
-                &RevealedAttributeInfo {
-                    sub_proof_index: attr_infos.sub_proof_index,
-                    raw: attr_info.raw.clone(),
-                    encoded: attr_info.encoded.clone(),
-                },
-            )?;
+            let attr_info = RevealedAttributeInfo {
+                sub_proof_index: attr_infos.sub_proof_index,
+                raw: attr_info.raw.clone(),
+                encoded: attr_info.encoded.clone(),
+            };
+            if let Err(err) = verify_revealed_attribute_value(attr_name, proof, &attr_info) {
+                add_revealed_value_failure(report, attr_referent, attr_name, &attr_info, err);
+            }
         }
     }
     Ok(())
 }
 
+fn add_revealed_value_failure(
+    report: &mut VerificationReport,
+    referent: &str,
+    attr_name: &str,
+    attr_info: &RevealedAttributeInfo,
+    err: Error,
+) {
+    add_referent_failure(
+        report,
+        referent,
+        VerificationFailure::RevealedValueMismatch {
+            attr_name: attr_name.to_string(),
+            sub_proof_index: attr_info.sub_proof_index,
+            reason: err.to_string(),
+        },
+    );
+}
+
 fn normalize_encoded_attr(attr: &str) -> Cow<'_, str> {
     attr.parse::<i32>()
         .map(|iattr| Cow::Owned(iattr.to_string()))
@@ -586,6 +1125,7 @@ fn verify_requested_restrictions(
     received_unrevealed_attrs: &HashMap<String, Identifier>,
     received_predicates: &HashMap<String, Identifier>,
     self_attested_attrs: &HashSet<String>,
+    report: &mut VerificationReport,
 ) -> Result<()> {
     let proof_attr_identifiers: HashMap<String, Identifier> = received_revealed_attrs
         .iter()
@@ -602,7 +1142,8 @@ fn verify_requested_restrictions(
 
     for (referent, info) in requested_attrs.iter() {
         if let Some(ref query) = info.restrictions {
-            let filter = gather_filter_info(referent, &proof_attr_identifiers)?;
+            let identifier = get_referent_identifier(referent, &proof_attr_identifiers)?;
+            let filter = Filter::new(&identifier.schema_id, &identifier.cred_def_id)?;
 
             let attr_value_map: HashMap<String, Option<&str>> = if let Some(name) =
                 info.name.as_ref()
@@ -637,16 +1178,16 @@ fn verify_requested_restrictions(
                 ));
             };
 
-            process_operator(&attr_value_map, query, &filter).map_err(err_map!(
-                "Requested restriction validation failed for \"{:?}\" attributes",
-                &attr_value_map
-            ))?;
+            if let Err(err) = process_operator(&attr_value_map, query, &filter) {
+                add_restriction_failure(report, referent, identifier, err);
+            }
         }
     }
 
     for (referent, info) in pres_req.requested_predicates.iter() {
         if let Some(ref query) = info.restrictions {
-            let filter = gather_filter_info(referent, received_predicates)?;
+            let identifier = get_referent_identifier(referent, received_predicates)?;
+            let filter = Filter::new(&identifier.schema_id, &identifier.cred_def_id)?;
 
             // start with the predicate requested attribute, which is un-revealed
             let mut attr_value_map = HashMap::new();
@@ -682,10 +1223,9 @@ fn verify_requested_restrictions(
                 }
             }
 
-            process_operator(&attr_value_map, query, &filter).map_err(err_map!(
-                "Requested restriction validation failed for \"{}\" predicate",
-                &info.name
-            ))?;
+            if let Err(err) = process_operator(&attr_value_map, query, &filter) {
+                add_restriction_failure(report, referent, identifier, err);
+            }
         }
     }
 
@@ -706,41 +1246,62 @@ fn is_self_attested(
     }
 }
 
-fn gather_filter_info(referent: &str, identifiers: &HashMap<String, Identifier>) -> Result<Filter> {
-    let identifier = identifiers.get(referent).ok_or_else(|| {
+fn get_referent_identifier<'a>(
+    referent: &str,
+    identifiers: &'a HashMap<String, Identifier>,
+) -> Result<&'a Identifier> {
+    identifiers.get(referent).ok_or_else(|| {
         err_msg!(
             InvalidState,
             "Identifier not found for referent: {}",
             referent
         )
-    })?;
+    })
+}
 
-    let (_, schema_issuer_did, schema_name, schema_version) =
-        identifier.schema_id.parts().ok_or_else(|| {
+fn add_restriction_failure(
+    report: &mut VerificationReport,
+    referent: &str,
+    identifier: &Identifier,
+    err: Error,
+) {
+    add_referent_failure(
+        report,
+        referent,
+        VerificationFailure::RestrictionMismatch {
+            schema_id: identifier.schema_id.clone(),
+            cred_def_id: identifier.cred_def_id.clone(),
+            reason: err.to_string(),
+        },
+    );
+}
+
+impl Filter {
+    pub(crate) fn new(schema_id: &SchemaId, cred_def_id: &CredentialDefinitionId) -> Result<Self> {
+        let (_, schema_issuer_did, schema_name, schema_version) =
+            schema_id.parts().ok_or_else(|| {
+                err_msg!("Invalid Schema ID `{}`: wrong number of parts", schema_id.0)
+            })?;
+
+        let issuer_did = cred_def_id.issuer_did().ok_or_else(|| {
             err_msg!(
-                "Invalid Schema ID `{}`: wrong number of parts",
-                identifier.schema_id.0
+                "Invalid Credential Definition ID `{}`: wrong number of parts",
+                cred_def_id.0
             )
         })?;
 
-    let issuer_did = identifier.cred_def_id.issuer_did().ok_or_else(|| {
-        err_msg!(
-            "Invalid Credential Definition ID `{}`: wrong number of parts",
-            identifier.cred_def_id.0
-        )
-    })?;
-
-    Ok(Filter {
-        schema_id: identifier.schema_id.0.to_string(),
-        schema_name,
-        schema_issuer_did: schema_issuer_did.0,
-        schema_version,
-        cred_def_id: identifier.cred_def_id.0.to_string(),
-        issuer_did: issuer_did.0,
-    })
+        Ok(Filter {
+            schema_id: schema_id.0.to_string(),
+            schema_name,
+            schema_issuer_did: schema_issuer_did.0,
+            schema_version,
+            cred_def_id: cred_def_id.0.to_string(),
+            issuer_did: issuer_did.0,
+        })
+    }
 }
 
-fn process_operator(
+pub(crate) fn process_operator(
     attr_value_map: &HashMap<String, Option<&str>>,
     restriction_op: &Query,
     filter: &Filter,
@@ -805,7 +1366,130 @@ fn process_operator(
                 ))
             }
         }
-        _ => Err(err_msg!(ProofRejected, "unsupported operator",)),
+        Query::Gt(ref tag_name, ref tag_value) => process_comparison(
+            "$gt",
+            attr_value_map,
+            tag_name,
+            tag_value,
+            filter,
+            |value| compare_values(value, tag_value) == Ordering::Greater,
+        ),
+        Query::Gte(ref tag_name, ref tag_value) => process_comparison(
+            "$gte",
+            attr_value_map,
+            tag_name,
+            tag_value,
+            filter,
+            |value| compare_values(value, tag_value) != Ordering::Less,
+        ),
+        Query::Lt(ref tag_name, ref tag_value) => process_comparison(
+            "$lt",
+            attr_value_map,
+            tag_name,
+            tag_value,
+            filter,
+            |value| compare_values(value, tag_value) == Ordering::Less,
+        ),
+        Query::Lte(ref tag_name, ref tag_value) => process_comparison(
+            "$lte",
+            attr_value_map,
+            tag_name,
+            tag_value,
+            filter,
+            |value| compare_values(value, tag_value) != Ordering::Greater,
+        ),
+        Query::Like(ref tag_name, ref pattern) => process_comparison(
+            "$like",
+            attr_value_map,
+            tag_name,
+            pattern,
+            filter,
+            |value| like_matches(value, pattern),
+        ),
+        Query::Exist(ref tag_names) => tag_names
+            .iter()
+            .map(|tag_name| {
+                filter_tag_value(attr_value_map, tag_name, filter)
+                    .map(|_| ())
+                    .map_err(err_map!(
+                        ProofRejected,
+                        "$exist operator validation failed for tag: \"{}\"",
+                        tag_name
+                    ))
+            })
+            .collect::<Result<Vec<()>>>()
+            .map(|_| ()),
+    }
+}
+
+/// Check an ordering or `$like` condition against the value of a tag.
+///
+/// Values are compared as integers when both are integers, segment by segment
+/// when both are dot-separated numbers such as schema versions, and otherwise
+/// as strings, as for `Query::matches`. Conditions on attribute values which
+/// are not revealed cannot be checked and are rejected.
+fn process_comparison(
+    operator: &str,
+    attr_value_map: &HashMap<String, Option<&str>>,
+    tag_name: &str,
+    tag_value: &str,
+    filter: &Filter,
+    accept: impl FnOnce(&str) -> bool,
+) -> Result<()> {
+    let value = filter_tag_value(attr_value_map, tag_name, filter)?.ok_or_else(|| {
+        err_msg!(
+            ProofRejected,
+            "{} operator validation failed for tag: \"{}\": attribute value is not revealed",
+            operator,
+            tag_name
+        )
+    })?;
+    if accept(value) {
+        Ok(())
+    } else {
+        Err(err_msg!(
+            ProofRejected,
+            "{} operator validation failed for tag: \"{}\", value: \"{}\", actual: \"{}\"",
+            operator,
+            tag_name,
+            tag_value,
+            value
+        ))
+    }
+}
+
+/// Find the value of a restriction tag for the presented credential. Attribute
+/// values which are not revealed are returned as `None`, and a marker tag has
+/// the value "1" when the attribute is presented.
+fn filter_tag_value<'a>(
+    attr_value_map: &HashMap<String, Option<&'a str>>,
+    tag: &str,
+    filter: &'a Filter,
+) -> Result<Option<&'a str>> {
+    match tag {
+        "schema_id" => Ok(Some(&filter.schema_id)),
+        "schema_issuer_did" => Ok(Some(&filter.schema_issuer_did)),
+        "schema_name" => Ok(Some(&filter.schema_name)),
+        "schema_version" => Ok(Some(&filter.schema_version)),
+        "cred_def_id" => Ok(Some(&filter.cred_def_id)),
+        "issuer_did" => Ok(Some(&filter.issuer_did)),
+        x => {
+            let captures = INTERNAL_TAG_MATCHER
+                .captures(x)
+                .ok_or_else(|| err_msg!("Unknown Filter Type"))?;
+            let value = attr_value_map.get(&captures[1]).ok_or_else(|| {
+                err_msg!(
+                    ProofRejected,
+                    "Attribute \"{}\" is not presented",
+                    &captures[1]
+                )
+            })?;
+            if &captures[2] == "marker" {
+                Ok(Some("1"))
+            } else {
+                Ok(*value)
+            }
+        }
     }
 }
 
@@ -867,12 +1551,17 @@ fn check_internal_tag_revealed_value(
     tag_value: &str,
     attr_value_map: &HashMap<String, Option<&str>>,
 ) -> Result<()> {
-    let attr_name = INTERNAL_TAG_MATCHER
+    let captures = INTERNAL_TAG_MATCHER
         .captures(key)
-        .ok_or_else(|| err_msg!(InvalidState, "Attribute name became unparseable",))?
+        .ok_or_else(|| err_msg!(InvalidState, "Attribute name became unparseable",))?;
+    let attr_name = captures
         .get(1)
         .ok_or_else(|| err_msg!(InvalidState, "No name has been parsed",))?
         .as_str();
+    // a marker only requires the presence of the attribute
+    if captures.get(2).is_some_and(|tag| tag.as_str() == "marker") {
+        return Ok(());
+    }
     if let Some(Some(revealed_value)) = attr_value_map.get(attr_name) {
         if *revealed_value != tag_value {
             return Err(err_msg!(
@@ -1202,6 +1891,71 @@ mod tests {
         assert!(_process_operator("zip", &op, &filter, Some("NOT HERE")).is_err());
     }
 
+    #[test]
+    fn test_process_op_marker_revealed_value() {
+        let filter = filter();
+
+        let op = Query::Eq(attr_tag(), "1".to_string());
+        _process_operator("zip", &op, &filter, Some("value")).unwrap();
+    }
+
+    #[test]
+    fn test_process_op_comparisons() {
+        let filter = filter();
+
+        // versions are compared segment by segment
+        let op = Query::Gte(schema_version_tag(), "1.2".to_string());
+        _process_operator("zip", &op, &filter, None).unwrap();
+        let op = Query::Gt(schema_version_tag(), "1.10".to_string());
+        assert!(_process_operator("zip", &op, &filter, None).is_err());
+        let op = Query::Lt(schema_version_tag(), "1.10".to_string());
+        _process_operator("zip", &op, &filter, None).unwrap();
+        let op = Query::Lte(schema_version_tag(), SCHEMA_VERSION.to_string());
+        _process_operator("zip", &op, &filter, None).unwrap();
+
+        // integers are compared numerically
+        let op = Query::Gt(attr_tag_value(), "9".to_string());
+        _process_operator("zip", &op, &filter, Some("10")).unwrap();
+        let op = Query::Lte(attr_tag_value(), "9".to_string());
+        assert!(_process_operator("zip", &op, &filter, Some("10")).is_err());
+
+        // other values are compared as strings
+        let op = Query::Lt(schema_name_tag(), "Schema Z".to_string());
+        _process_operator("zip", &op, &filter, None).unwrap();
+
+        // attribute values which are not revealed cannot be compared
+        let op = Query::Gt(attr_tag_value(), "9".to_string());
+        assert!(_process_operator("zip", &op, &filter, None).is_err());
+        let op = Query::Gt("attr::other::value".to_string(), "9".to_string());
+        assert!(_process_operator("zip", &op, &filter, Some("10")).is_err());
+    }
+
+    #[test]
+    fn test_process_op_like() {
+        let filter = filter();
+
+        let op = Query::Like(schema_name_tag(), "Schema%".to_string());
+        _process_operator("zip", &op, &filter, None).unwrap();
+        let op = Query::Like(cred_def_id_tag(), "3_5".to_string());
+        _process_operator("zip", &op, &filter, None).unwrap();
+        let op = Query::Like(schema_name_tag(), "schema%".to_string());
+        assert!(_process_operator("zip", &op, &filter, None).is_err());
+        let op = Query::Like(attr_tag_value(), "%1%".to_string());
+        _process_operator("zip", &op, &filter, Some("01234")).unwrap();
+    }
+
+    #[test]
+    fn test_process_op_exist() {
+        let filter = filter();
+
+        let op = Query::Exist(vec![schema_id_tag(), attr_tag(), attr_tag_value()]);
+        _process_operator("zip", &op, &filter, None).unwrap();
+        let op = Query::Exist(vec!["attr::other::marker".to_string()]);
+        assert!(_process_operator("zip", &op, &filter, None).is_err());
+        let op = Query::Exist(vec![bad_attr_tag()]);
+        assert!(_process_operator("zip", &op, &filter, None).is_err());
+    }
+
     fn _received() -> HashMap<String, Identifier> {
         let mut res: HashMap<String, Identifier> = HashMap::new();
         res.insert(
@@ -1234,16 +1988,89 @@ mod tests {
 
     #[test]
     fn validate_timestamp_works() {
-        validate_timestamp(&_received(), "referent_1", &None, &None).unwrap();
-        validate_timestamp(&_received(), "referent_1", &Some(_interval()), &None).unwrap();
-        validate_timestamp(&_received(), "referent_1", &None, &Some(_interval())).unwrap();
+        let rule = TimestampRule::default();
+        validate_timestamp(&_received(), "referent_1", &None, &None, rule).unwrap();
+        validate_timestamp(&_received(), "referent_1", &Some(_interval()), &None, rule).unwrap();
+        validate_timestamp(&_received(), "referent_1", &None, &Some(_interval()), rule).unwrap();
     }
 
     #[test]
     fn validate_timestamp_not_work() {
-        validate_timestamp(&_received(), "referent_2", &Some(_interval()), &None).unwrap_err();
-        validate_timestamp(&_received(), "referent_2", &None, &Some(_interval())).unwrap_err();
-        validate_timestamp(&_received(), "referent_3", &None, &Some(_interval())).unwrap_err();
+        let rule = TimestampRule::default();
+        validate_timestamp(&_received(), "referent_2", &Some(_interval()), &None, rule)
+            .unwrap_err();
+        validate_timestamp(&_received(), "referent_2", &None, &Some(_interval()), rule)
+            .unwrap_err();
+        validate_timestamp(&_received(), "referent_3", &None, &Some(_interval()), rule)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn validate_timestamp_after_interval() {
+        let interval = Some(NonRevocedInterval {
+            from: None,
+            to: Some(1000),
+        });
+        validate_timestamp(
+            &_received(),
+            "referent_1",
+            &interval,
+            &None,
+            TimestampRule::NotAfterInterval,
+        )
+        .unwrap_err();
+        validate_timestamp(
+            &_received(),
+            "referent_1",
+            &interval,
+            &None,
+            TimestampRule::Present,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn validate_timestamp_within_interval() {
+        let interval = Some(NonRevocedInterval {
+            from: Some(1000),
+            to: Some(2000),
+        });
+        validate_timestamp(
+            &_received(),
+            "referent_1",
+            &interval,
+            &None,
+            TimestampRule::WithinInterval,
+        )
+        .unwrap();
+        let interval = Some(NonRevocedInterval {
+            from: Some(1500),
+            to: Some(2000),
+        });
+        validate_timestamp(
+            &_received(),
+            "referent_1",
+            &interval,
+            &None,
+            TimestampRule::NotAfterInterval,
+        )
+        .unwrap();
+        validate_timestamp(
+            &_received(),
+            "referent_1",
+            &interval,
+            &None,
+            TimestampRule::WithinInterval,
+        )
+        .unwrap_err();
+        validate_timestamp(
+            &_received(),
+            "referent_2",
+            &interval,
+            &None,
+            TimestampRule::Present,
+        )
+        .unwrap_err();
     }
 
     #[test]
@@ -1,18 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use indy_credx::{
     issuer, prover,
-    tails::{TailsFileReader, TailsFileWriter},
+    resolver::{CachingResolver, InMemoryResolver},
+    tails::{TailsBufferReader, TailsBufferWriter, TailsFileReader, TailsFileWriter},
     types::{
-        CredentialDefinitionConfig, CredentialRevocationConfig, IssuanceType, MakeCredentialValues,
-        PresentCredentials, RegistryType, RevocationRegistryDefinition, SignatureType,
+        CredentialBatchEntry, CredentialDefinitionConfig, CredentialRevocationConfig, IssuanceType,
+        MakeCredentialValues, PresentCredentials, Presentation, PresentationRequest, ProofStatus,
+        RegistryType, RevocationRegistry, RevocationRegistryDefinition, SchemaId, SignatureType,
+        TimestampRule, VerificationFailure, VerificationPolicy,
     },
-    verifier,
+    utils::AttributeEncoding,
+    verifier, ErrorKind,
 };
 
+use indy_data_types::anoncreds::{credential_store::CredentialStore, wql::Query};
 use serde_json::json;
 
-use self::utils::anoncreds::{IssuerWallet, ProverWallet};
+use self::utils::anoncreds::{ledger, raw_values, IssuerWallet, ProverWallet};
 
 mod utils;
 
@@ -213,6 +218,30 @@ fn anoncreds_works_for_single_issuer_single_prover() {
     )
     .expect("Error verifying presentation");
     assert!(valid);
+
+    // The deprecated legacy functions apply VerificationPolicy::legacy
+    #[allow(deprecated)]
+    let valid = verifier::verify_presentation_legacy(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .expect("Error verifying presentation");
+    assert!(valid);
+    #[allow(deprecated)]
+    let report = verifier::verify_presentation_report_legacy(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .expect("Error verifying presentation");
+    assert!(report.is_valid());
 }
 
 #[test]
@@ -356,7 +385,7 @@ fn anoncreds_works_for_single_issuer_single_prover_unrevoked() {
     // Prover creates revocation state
     let tails_reader = TailsFileReader::new(tails_path);
     let rev_state = prover::create_or_update_revocation_state(
-        tails_reader,
+        &tails_reader,
         &rev_reg_def,
         &rev_reg_delta,
         1,
@@ -416,4 +445,936 @@ fn anoncreds_works_for_single_issuer_single_prover_unrevoked() {
     )
     .expect("Error verifying presentation");
     assert!(valid);
+
+    // The verifier may provide a history of registry entries, from which the
+    // state in effect at the presented timestamp is selected
+    let (revoked_reg, _) = issuer::revoke_credential(
+        gvt_cred_def,
+        &rev_reg_def,
+        &rev_reg_def_private,
+        &rev_reg,
+        1,
+    )
+    .expect("Error revoking credential");
+    let history_request = serde_json::from_value(json!({
+        "nonce": nonce,
+        "name":"pres_req_1",
+        "version":"0.1",
+        "requested_attributes":{
+            "attr1_referent":{"name":"name"}
+        },
+        "requested_predicates":{},
+        "non_revoked": {"to": 10}
+    }))
+    .expect("Error creating proof request");
+    let rev_state = prover::create_or_update_revocation_state(
+        &tails_reader,
+        &rev_reg_def,
+        &rev_reg_delta,
+        1,
+        5,
+        None,
+    )
+    .expect("Error creating revocation state");
+    let mut present = PresentCredentials::default();
+    present
+        .add_credential(&prover_wallet.credentials[0], Some(5), Some(&rev_state))
+        .add_requested_attribute("attr1_referent", true);
+    let presentation = prover::create_presentation(
+        &history_request,
+        present,
+        None,
+        &prover_wallet.link_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+
+    let interval_policy = VerificationPolicy {
+        timestamp_rule: TimestampRule::NotAfterInterval,
+        ..VerificationPolicy::basic()
+    };
+    let verify_with_history = |history: &[(u64, &RevocationRegistry)]| {
+        let rev_regs = HashMap::from([(
+            rev_reg_def.id().clone(),
+            history.iter().copied().collect::<HashMap<_, _>>(),
+        )]);
+        verifier::verify_presentation_report(
+            &presentation,
+            &history_request,
+            &schemas,
+            &cred_defs,
+            Some(&reg_defs),
+            Some(&rev_regs),
+            &interval_policy,
+        )
+        .expect("Error verifying presentation")
+    };
+
+    // The state published at 1 remains in effect until after the interval
+    let report = verify_with_history(&[(1, &rev_reg), (12, &revoked_reg)]);
+    assert!(report.is_valid());
+
+    // The state published at 1 was replaced within the interval
+    let report = verify_with_history(&[(1, &rev_reg), (7, &revoked_reg)]);
+    assert_eq!(report.proof, ProofStatus::Verified);
+    assert!(matches!(
+        report.referents["attr1_referent"].failures.as_slice(),
+        [VerificationFailure::RegistryStateOutOfInterval {
+            timestamp: 5,
+            published: 1,
+            replaced: Some(7),
+            ..
+        }]
+    ));
+
+    // The state in effect at the presented timestamp revokes the credential
+    let report = verify_with_history(&[(1, &rev_reg), (3, &revoked_reg)]);
+    assert_eq!(report.proof, ProofStatus::Rejected);
+    assert!(matches!(
+        report.sub_proofs[0].failures.as_slice(),
+        [VerificationFailure::RevocationProofRejected { timestamp: 5, .. }]
+    ));
+    let revoked_regs = HashMap::from([(
+        rev_reg_def.id().clone(),
+        HashMap::from([(1, &rev_reg), (3, &revoked_reg)]),
+    )]);
+    assert!(!verifier::verify_presentation(
+        &presentation,
+        &history_request,
+        &schemas,
+        &cred_defs,
+        Some(&reg_defs),
+        Some(&revoked_regs),
+    )
+    .expect("Error verifying presentation"));
+
+    // No state was published at or before the presented timestamp
+    let report = verify_with_history(&[(6, &rev_reg)]);
+    assert_eq!(report.proof, ProofStatus::NotChecked);
+
+    // The bool API only requires a timestamp to be present, while a policy with
+    // the NotAfterInterval rule rejects a timestamp after the end of the interval
+    let early_request: PresentationRequest = serde_json::from_value(json!({
+        "nonce": nonce,
+        "name":"pres_req_1",
+        "version":"0.1",
+        "requested_attributes":{
+            "attr1_referent":{"name":"name"}
+        },
+        "requested_predicates":{},
+        "non_revoked": {"to": 3}
+    }))
+    .expect("Error creating proof request");
+    let rev_regs = HashMap::from([(rev_reg_def.id().clone(), HashMap::from([(5, &rev_reg)]))]);
+    assert!(verifier::verify_presentation(
+        &presentation,
+        &early_request,
+        &schemas,
+        &cred_defs,
+        Some(&reg_defs),
+        Some(&rev_regs),
+    )
+    .expect("Error verifying presentation"));
+    let report = verifier::verify_presentation_report(
+        &presentation,
+        &early_request,
+        &schemas,
+        &cred_defs,
+        Some(&reg_defs),
+        Some(&rev_regs),
+        &interval_policy,
+    )
+    .expect("Error verifying presentation");
+    assert!(matches!(
+        report.referents["attr1_referent"].failures.as_slice(),
+        [
+            VerificationFailure::TimestampOutOfInterval {
+                timestamp: Some(5),
+                ..
+            },
+            ..
+        ]
+    ));
+}
+
+#[test]
+fn anoncreds_works_for_batch_issuance() {
+    let mut issuer_wallet = IssuerWallet::default();
+    let mut prover_wallet = ProverWallet::default();
+    let gvt_schema = issuer_wallet.create_cred_def(GVT_SCHEMA_NAME, GVT_SCHEMA_ATTRIBUTES, true);
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+
+    // Create revocation registry definition and initial registry, keeping the tails in memory
+    let mut tails_writer = TailsBufferWriter::new(None);
+    let (rev_reg_def, rev_reg_def_private, rev_reg, rev_reg_init_delta) =
+        issuer::create_revocation_registry(
+            &issuer_wallet.did,
+            gvt_cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_ON_DEMAND,
+            5,
+            &mut tails_writer,
+        )
+        .expect("Error creating revocation registry definition");
+    let (tails, _tails_hash) = tails_writer.into_tails().expect("Missing tails");
+
+    // Prover creates a Credential Request for each Credential Offer
+    let mut offers = Vec::new();
+    for _ in 0..2 {
+        let cred_offer = issuer::create_credential_offer(
+            gvt_schema.id(),
+            gvt_cred_def,
+            &issuer_wallet.cred_defs[0].key_proof,
+        )
+        .expect("Error creating credential offer");
+        let (cred_request, cred_request_metadata) = prover::create_credential_request(
+            &prover_wallet.did,
+            gvt_cred_def,
+            &prover_wallet.link_secret,
+            "default",
+            &cred_offer,
+        )
+        .expect("Error creating credential request");
+        offers.push((cred_offer, cred_request, cred_request_metadata));
+    }
+
+    let make_entries = |indices: [u32; 2]| {
+        offers
+            .iter()
+            .zip(indices)
+            .zip([("Alex", "28"), ("Bob", "35")])
+            .map(
+                |(((cred_offer, cred_request, _), registry_idx), (name, age))| {
+                    CredentialBatchEntry {
+                        cred_offer,
+                        cred_request,
+                        cred_values: raw_values(&[
+                            ("sex", "male"),
+                            ("name", name),
+                            ("height", "175"),
+                            ("age", age),
+                        ])
+                        .into(),
+                        registry_idx,
+                    }
+                },
+            )
+            .collect::<Vec<_>>()
+    };
+
+    // Invalid revocation indices are rejected before any credential is issued
+    for indices in [[2, 2], [0, 1], [1, 6]] {
+        let err = issuer::create_credential_batch(
+            gvt_cred_def,
+            &issuer_wallet.cred_defs[0].private,
+            &rev_reg_def,
+            &rev_reg_def_private,
+            &rev_reg,
+            &HashSet::new(),
+            make_entries(indices),
+        )
+        .expect_err("Expected invalid revocation index");
+        assert_eq!(err.kind(), ErrorKind::InvalidUserRevocId);
+    }
+
+    // Indices which were already assigned to credentials are rejected
+    let err = issuer::create_credential_batch(
+        gvt_cred_def,
+        &issuer_wallet.cred_defs[0].private,
+        &rev_reg_def,
+        &rev_reg_def_private,
+        &rev_reg,
+        &HashSet::from([3]),
+        make_entries([1, 3]),
+    )
+    .expect_err("Expected used revocation index");
+    assert_eq!(err.kind(), ErrorKind::InvalidUserRevocId);
+
+    // Issuer creates the credentials in one batch
+    let (issued_creds, rev_reg, rev_reg_delta) = issuer::create_credential_batch(
+        gvt_cred_def,
+        &issuer_wallet.cred_defs[0].private,
+        &rev_reg_def,
+        &rev_reg_def_private,
+        &rev_reg,
+        &HashSet::from([2]),
+        make_entries([1, 3]),
+    )
+    .expect("Error creating credentials");
+    assert_eq!(issued_creds.len(), 2);
+    let rev_reg_delta = rev_reg_delta.expect("Missing registry delta");
+    let delta_json = serde_json::to_value(&rev_reg_delta).expect("Error serializing delta");
+    let mut delta_issued: Vec<u32> =
+        serde_json::from_value(delta_json["value"]["issued"].clone()).expect("Invalid delta");
+    delta_issued.sort_unstable();
+    assert_eq!(delta_issued, [1, 3]);
+
+    // Prover receives the credentials and processes them
+    for (mut recv_cred, (_, _, cred_request_metadata)) in issued_creds.into_iter().zip(&offers) {
+        prover::process_credential(
+            &mut recv_cred,
+            cred_request_metadata,
+            &prover_wallet.link_secret,
+            gvt_cred_def,
+            Some(&rev_reg_def),
+        )
+        .expect("Error processing credential");
+        prover_wallet.credentials.push(recv_cred);
+    }
+
+    // Verifier creates a presentation request
+    let nonce = verifier::generate_nonce().expect("Error generating presentation request nonce");
+    let pres_request = serde_json::from_value(json!({
+        "nonce": nonce,
+        "name":"pres_req_1",
+        "version":"0.1",
+        "requested_attributes":{
+            "attr1_referent":{
+                "name":"name"
+            }
+        },
+        "requested_predicates":{
+            "predicate1_referent":{"name":"age","p_type":">=","p_value":30}
+        },
+        "non_revoked": {"from": 10, "to": 10}
+    }))
+    .expect("Error creating proof request");
+
+    // Prover creates revocation state for the second credential from the published deltas
+    let full_delta = issuer::merge_revocation_registry_deltas(&rev_reg_init_delta, &rev_reg_delta)
+        .expect("Error merging deltas");
+    let tails_reader = TailsBufferReader::new(tails).expect("Error reading tails");
+    let rev_state = prover::create_or_update_revocation_state(
+        &tails_reader,
+        &rev_reg_def,
+        &full_delta,
+        3,
+        10,
+        None,
+    )
+    .expect("Error creating revocation state");
+
+    // Prover creates presentation
+    let mut present = PresentCredentials::default();
+    {
+        let mut cred =
+            present.add_credential(&prover_wallet.credentials[1], Some(10), Some(&rev_state));
+        cred.add_requested_attribute("attr1_referent", true);
+        cred.add_requested_predicate("predicate1_referent");
+    }
+
+    let (schemas, cred_defs) = ledger(&gvt_schema, gvt_cred_def);
+    let presentation = prover::create_presentation(
+        &pres_request,
+        present,
+        None,
+        &prover_wallet.link_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+
+    // Verifier verifies presentation against the final registry
+    let mut reg_defs = HashMap::new();
+    reg_defs.insert(rev_reg_def.id().clone(), &rev_reg_def);
+    let mut rev_regs = HashMap::new();
+    let mut rev_entry = HashMap::new();
+    rev_entry.insert(10, &rev_reg);
+    rev_regs.insert(rev_reg_def.id().clone(), rev_entry);
+    let valid = verifier::verify_presentation(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        Some(&reg_defs),
+        Some(&rev_regs),
+    )
+    .expect("Error verifying presentation");
+    assert!(valid);
+}
+
+#[test]
+fn anoncreds_works_for_unrevoked_credential() {
+    let mut issuer_wallet = IssuerWallet::default();
+    issuer_wallet.create_cred_def(GVT_SCHEMA_NAME, GVT_SCHEMA_ATTRIBUTES, true);
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+
+    // Create revocation registry definition and initial registry
+    let mut tails_writer = TailsBufferWriter::new(None);
+    let (rev_reg_def, rev_reg_def_private, rev_reg, _rev_reg_delta) =
+        issuer::create_revocation_registry(
+            &issuer_wallet.did,
+            gvt_cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_BY_DEFAULT,
+            5,
+            &mut tails_writer,
+        )
+        .expect("Error creating revocation registry definition");
+
+    // Issuer revokes a credential by mistake
+    let (revoked_reg, _revoke_delta) = issuer::revoke_credential(
+        gvt_cred_def,
+        &rev_reg_def,
+        &rev_reg_def_private,
+        &rev_reg,
+        2,
+    )
+    .expect("Error revoking credential");
+
+    let issued = BTreeSet::new();
+    let revoked = BTreeSet::from([2]);
+
+    // Only a revoked index may be restored
+    let err = issuer::unrevoke_credential(
+        gvt_cred_def,
+        &rev_reg_def,
+        &rev_reg_def_private,
+        &revoked_reg,
+        3,
+        &issued,
+        &revoked,
+    )
+    .expect_err("Expected error restoring unrevoked index");
+    assert_eq!(err.kind(), ErrorKind::InvalidUserRevocId);
+
+    // Issuer restores the credential
+    let (restored_reg, delta) = issuer::unrevoke_credential(
+        gvt_cred_def,
+        &rev_reg_def,
+        &rev_reg_def_private,
+        &revoked_reg,
+        2,
+        &issued,
+        &revoked,
+    )
+    .expect("Error restoring credential");
+    let delta_json = serde_json::to_value(&delta).expect("Error serializing delta");
+    assert_eq!(delta_json["value"]["issued"], json!([2]));
+    let accum = |reg: &RevocationRegistry| match reg {
+        RevocationRegistry::RevocationRegistryV1(v1) => v1.value.accum,
+    };
+    assert_ne!(accum(&revoked_reg), accum(&rev_reg));
+    assert_eq!(accum(&restored_reg), accum(&rev_reg));
+}
+
+#[test]
+fn anoncreds_works_for_typed_attributes() {
+    let mut issuer_wallet = IssuerWallet::default();
+    let mut prover_wallet = ProverWallet::default();
+    let schema = issuer_wallet.create_cred_def(
+        "license",
+        &["name", "birthdate", "expiry", "balance"],
+        false,
+    );
+
+    // Typed encodings support predicates and can be decoded after presentation
+    let balance = AttributeEncoding::Decimal { scale: 2 };
+    let mut cred_values = raw_values(&[("name", "Alex")]);
+    cred_values
+        .add_typed("birthdate", "1995-06-30", AttributeEncoding::Date)
+        .expect("Error encoding attribute");
+    cred_values
+        .add_typed("expiry", "2030-01-01", AttributeEncoding::Date)
+        .expect("Error encoding attribute");
+    cred_values
+        .add_typed("balance", "1024.50", balance)
+        .expect("Error encoding attribute");
+    issuer_wallet.issue_credential(&schema, &mut prover_wallet, cred_values);
+
+    let encode_date = |date| {
+        AttributeEncoding::Date
+            .encode_predicate_value(date)
+            .expect("Error encoding predicate value")
+    };
+    let nonce = verifier::generate_nonce().expect("Error generating presentation request nonce");
+    let pres_request = serde_json::from_value(json!({
+        "nonce": nonce,
+        "name":"pres_req_1",
+        "version":"0.1",
+        "requested_attributes":{
+            "attr1_referent":{"name":"balance"}
+        },
+        "requested_predicates":{
+            "adult_referent":{"name":"birthdate","p_type":"<=","p_value":encode_date("2008-10-18")},
+            "valid_referent":{"name":"expiry","p_type":">","p_value":encode_date("2026-10-18")}
+        }
+    }))
+    .expect("Error creating proof request");
+
+    let mut present = PresentCredentials::default();
+    {
+        let mut cred = present.add_credential(&prover_wallet.credentials[0], None, None);
+        cred.add_requested_attribute("attr1_referent", true);
+        cred.add_requested_predicate("adult_referent");
+        cred.add_requested_predicate("valid_referent");
+    }
+
+    let (schemas, cred_defs) = ledger(&schema, &issuer_wallet.cred_defs[0].public);
+
+    let presentation = prover::create_presentation(
+        &pres_request,
+        present,
+        None,
+        &prover_wallet.link_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+
+    let revealed = &presentation.requested_proof.revealed_attrs["attr1_referent"];
+    assert_eq!(balance.decode(&revealed.encoded).unwrap(), revealed.raw);
+    let valid = verifier::verify_presentation(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .expect("Error verifying presentation");
+    assert!(valid);
+}
+
+#[test]
+fn anoncreds_works_for_credential_selection() {
+    let mut issuer_wallet = IssuerWallet::default();
+    let mut prover_wallet = ProverWallet::default();
+    let gvt_schema = issuer_wallet.create_cred_def(GVT_SCHEMA_NAME, GVT_SCHEMA_ATTRIBUTES, false);
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+
+    // Issuer creates two credentials for the prover
+    for (name, age) in [("Alex", "16"), ("Sam", "28")] {
+        issuer_wallet.issue_credential(
+            &gvt_schema,
+            &mut prover_wallet,
+            raw_values(&[
+                ("name", name),
+                ("age", age),
+                ("sex", "male"),
+                ("height", "175"),
+            ]),
+        );
+    }
+
+    let nonce = verifier::generate_nonce().expect("Error generating presentation request nonce");
+    let pres_request = serde_json::from_value(json!({
+        "nonce": nonce,
+        "name":"pres_req_1",
+        "version":"0.1",
+        "requested_attributes":{
+            "attr1_referent":{
+                "name":"name",
+                "restrictions":{"cred_def_id": gvt_cred_def.id()}
+            },
+            "attr2_referent":{
+                "names":["name", "sex"],
+                "restrictions":{"attr::name::value": "Alex", "attr::sex::marker": "1"}
+            },
+            "attr3_referent":{"name":"phone"}
+        },
+        "requested_predicates":{
+            "predicate1_referent":{
+                "name":"age",
+                "p_type":">=",
+                "p_value":18,
+                "restrictions":{"schema_name": GVT_SCHEMA_NAME}
+            }
+        }
+    }))
+    .expect("Error creating proof request");
+
+    // Prover finds the credentials which match each referent
+    let credentials: Vec<_> = prover_wallet.credentials.iter().collect();
+    let matches =
+        prover::match_credentials(&pres_request, &credentials).expect("Error matching credentials");
+    assert_eq!(matches.attributes["attr1_referent"], [0, 1]);
+    assert_eq!(matches.attributes["attr2_referent"], [0]);
+    assert!(matches.attributes["attr3_referent"].is_empty());
+    assert_eq!(matches.predicates["predicate1_referent"], [1]);
+    assert_eq!(matches.unmatched(), ["attr3_referent"]);
+
+    // A credential with a malformed identifier is reported rather than skipped
+    let mut malformed = prover_wallet.credentials[1]
+        .try_clone()
+        .expect("Error cloning credential");
+    malformed.schema_id = SchemaId("schema".to_string());
+    let err = prover::match_credentials(&pres_request, &[credentials[0], &malformed])
+        .expect_err("Expected error matching a malformed credential");
+    assert_eq!(err.kind(), ErrorKind::Input);
+    assert!(err.to_string().contains("credential 1"), "{}", err);
+
+    // Prover searches the stored credentials using their tags
+    let mut store = CredentialStore::new();
+    for (idx, credential) in prover_wallet.credentials.iter().enumerate() {
+        store.insert(
+            format!("cred{}", idx),
+            credential.try_clone().expect("Error cloning credential"),
+        );
+    }
+    let tags = store.tags("cred0").expect("Missing credential tags");
+    for tag in [
+        "issuer_did",
+        "cred_def_id",
+        "schema_id",
+        "schema_issuer_did",
+    ] {
+        assert!(tags.contains_key(tag));
+    }
+    assert_eq!(tags["attr::name::value"], "Alex");
+    let query: Query = serde_json::from_value(json!({
+        "schema_name": GVT_SCHEMA_NAME,
+        "attr::age::value": {"$gte": "18"},
+        "attr::name::value": {"$like": "S%"}
+    }))
+    .expect("Error parsing query");
+    let found: Vec<&str> = store.search(&query).map(|(id, _)| id).collect();
+    assert_eq!(found, ["cred1"]);
+
+    // Prover presents the default selection
+    let selection = matches.select_first();
+    let present = selection
+        .present_credentials(&credentials, &HashMap::new())
+        .expect("Error building presented credentials");
+
+    let (schemas, cred_defs) = ledger(&gvt_schema, gvt_cred_def);
+    let mut self_attested = HashMap::new();
+    self_attested.insert("attr3_referent".to_string(), "8-800-300".to_string());
+
+    let presentation = prover::create_presentation(
+        &pres_request,
+        present,
+        Some(self_attested),
+        &prover_wallet.link_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+    assert_eq!(
+        "Alex",
+        presentation.requested_proof.revealed_attrs["attr1_referent"].raw
+    );
+    let valid = verifier::verify_presentation(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .expect("Error verifying presentation");
+    assert!(valid);
+}
+
+#[test]
+fn anoncreds_works_for_verification_report() {
+    let mut issuer_wallet = IssuerWallet::default();
+    let mut prover_wallet = ProverWallet::default();
+    let gvt_schema = issuer_wallet.create_cred_def(GVT_SCHEMA_NAME, GVT_SCHEMA_ATTRIBUTES, false);
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+    issuer_wallet.issue_credential(
+        &gvt_schema,
+        &mut prover_wallet,
+        raw_values(&[
+            ("sex", "male"),
+            ("name", "Alex"),
+            ("height", "175"),
+            ("age", "28"),
+        ]),
+    );
+
+    let nonce = verifier::generate_nonce().expect("Error generating presentation request nonce");
+    let request_json = |restrictions| {
+        json!({
+            "nonce": nonce,
+            "name":"pres_req_1",
+            "version":"0.1",
+            "requested_attributes":{
+                "attr1_referent":{
+                    "name":"name",
+                    "restrictions": restrictions
+                }
+            },
+            "requested_predicates":{
+                "predicate1_referent":{"name":"age","p_type":">=","p_value":18}
+            }
+        })
+    };
+    let pres_request = serde_json::from_value(request_json(json!({
+        "cred_def_id": gvt_cred_def.id().0
+    })))
+    .expect("Error creating proof request");
+
+    let mut present = PresentCredentials::default();
+    {
+        let mut cred1 = present.add_credential(&prover_wallet.credentials[0], None, None);
+        cred1.add_requested_attribute("attr1_referent", true);
+        cred1.add_requested_predicate("predicate1_referent");
+    }
+
+    let (schemas, cred_defs) = ledger(&gvt_schema, gvt_cred_def);
+
+    let presentation = prover::create_presentation(
+        &pres_request,
+        present,
+        None,
+        &prover_wallet.link_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+
+    let report = verifier::verify_presentation_report(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+        &VerificationPolicy::default(),
+    )
+    .expect("Error verifying presentation");
+    assert!(report.is_valid());
+    assert_eq!(report.proof, ProofStatus::Verified);
+    assert_eq!(report.referents["attr1_referent"].sub_proof_index, Some(0));
+    assert_eq!(
+        report.sub_proofs[0].identifier.cred_def_id,
+        *gvt_cred_def.id()
+    );
+
+    // The same presentation is verified with ledger objects resolved on demand
+    let mut resolver = InMemoryResolver::new();
+    resolver.add_schema(gvt_schema.clone());
+    resolver
+        .add_cred_def(serde_json::from_value(serde_json::to_value(gvt_cred_def).unwrap()).unwrap());
+    let resolver = CachingResolver::new(resolver);
+    let report = verifier::verify_presentation_report_with_resolver(
+        &presentation,
+        &pres_request,
+        &resolver,
+        &VerificationPolicy::default(),
+    )
+    .expect("Error verifying presentation");
+    assert!(report.is_valid());
+
+    // A request restricted to another issuer reports the credential identifiers
+    let other_request = serde_json::from_value(request_json(json!({
+        "issuer_did": "VsKV7grR1BUE29mG2Fm2kX"
+    })))
+    .expect("Error creating proof request");
+    let report = verifier::verify_presentation_report(
+        &presentation,
+        &other_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+        &VerificationPolicy::default(),
+    )
+    .expect("Error verifying presentation");
+    assert!(!report.is_valid());
+    assert_eq!(report.proof, ProofStatus::Verified);
+    assert!(report.referents["predicate1_referent"].failures.is_empty());
+    match report.referents["attr1_referent"].failures.as_slice() {
+        [VerificationFailure::RestrictionMismatch {
+            schema_id,
+            cred_def_id,
+            ..
+        }] => {
+            assert_eq!(schema_id, gvt_schema.id());
+            assert_eq!(cred_def_id, gvt_cred_def.id());
+        }
+        failures => panic!("Unexpected failures: {:?}", failures),
+    }
+    // The bool API reports restriction failures as errors
+    let err = verifier::verify_presentation(
+        &presentation,
+        &other_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .expect_err("Expected restriction failure");
+    assert_eq!(err.kind(), ErrorKind::Input);
+
+    // A revealed value which does not match the proof is reported
+    let mut tampered: Presentation =
+        serde_json::from_value(serde_json::to_value(&presentation).unwrap()).unwrap();
+    tampered
+        .requested_proof
+        .revealed_attrs
+        .get_mut("attr1_referent")
+        .unwrap()
+        .encoded = "1".to_string();
+    let report = verifier::verify_presentation_report(
+        &tampered,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+        &VerificationPolicy::default(),
+    )
+    .expect("Error verifying presentation");
+    assert!(!report.is_valid());
+    assert!(matches!(
+        report.referents["attr1_referent"].failures.as_slice(),
+        [VerificationFailure::RevealedValueMismatch { attr_name, sub_proof_index: 0, .. }]
+            if attr_name == "name"
+    ));
+    let err =
+        verifier::verify_presentation(&tampered, &pres_request, &schemas, &cred_defs, None, None)
+            .expect_err("Expected revealed value failure");
+    assert_eq!(err.kind(), ErrorKind::ProofRejected);
+
+    // The policy may require fully-qualified identifiers
+    let policy = VerificationPolicy {
+        require_qualified_identifiers: true,
+        ..Default::default()
+    };
+    let report = verifier::verify_presentation_report(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+        &policy,
+    )
+    .expect("Error verifying presentation");
+    assert!(!report.is_valid());
+    assert_eq!(report.proof, ProofStatus::Verified);
+    assert_eq!(report.sub_proofs[0].failures.len(), 2);
+
+    // The policy may forbid self-attested attributes
+    let self_attested_request = serde_json::from_value(json!({
+        "nonce": nonce,
+        "name":"pres_req_1",
+        "version":"0.1",
+        "requested_attributes":{
+            "attr1_referent":{"name":"phone"}
+        },
+        "requested_predicates":{
+            "predicate1_referent":{"name":"age","p_type":">=","p_value":18}
+        }
+    }))
+    .expect("Error creating proof request");
+    let mut present = PresentCredentials::default();
+    present
+        .add_credential(&prover_wallet.credentials[0], None, None)
+        .add_requested_predicate("predicate1_referent");
+    let presentation = prover::create_presentation(
+        &self_attested_request,
+        present,
+        Some(HashMap::from([(
+            "attr1_referent".to_string(),
+            "8-800-300".to_string(),
+        )])),
+        &prover_wallet.link_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+    let policy = serde_json::from_value(json!({"forbid_self_attested": true}))
+        .expect("Error parsing verification policy");
+    assert!(verifier::verify_presentation(
+        &presentation,
+        &self_attested_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .expect("Error verifying presentation"));
+    let report = verifier::verify_presentation_report(
+        &presentation,
+        &self_attested_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+        &policy,
+    )
+    .expect("Error verifying presentation");
+    assert_eq!(
+        report.referents["attr1_referent"].failures,
+        [VerificationFailure::SelfAttestedAttribute]
+    );
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn anoncreds_works_for_batch_verification() {
+    let mut issuer_wallet = IssuerWallet::default();
+    let mut prover_wallet = ProverWallet::default();
+    let gvt_schema = issuer_wallet.create_cred_def(GVT_SCHEMA_NAME, GVT_SCHEMA_ATTRIBUTES, false);
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+    issuer_wallet.issue_credential(
+        &gvt_schema,
+        &mut prover_wallet,
+        raw_values(&[
+            ("sex", "male"),
+            ("name", "Alex"),
+            ("height", "175"),
+            ("age", "28"),
+        ]),
+    );
+
+    let mut resolver = InMemoryResolver::new();
+    resolver.add_schema(gvt_schema.clone());
+    resolver
+        .add_cred_def(serde_json::from_value(serde_json::to_value(gvt_cred_def).unwrap()).unwrap());
+
+    let mut requests = Vec::new();
+    let mut presentations = Vec::new();
+    for _ in 0..4 {
+        let nonce =
+            verifier::generate_nonce().expect("Error generating presentation request nonce");
+        let pres_request: indy_credx::types::PresentationRequest = serde_json::from_value(json!({
+            "nonce": nonce,
+            "name":"pres_req_1",
+            "version":"0.1",
+            "requested_attributes":{
+                "attr1_referent":{"name":"name"}
+            },
+            "requested_predicates":{
+                "predicate1_referent":{"name":"age","p_type":">=","p_value":18}
+            }
+        }))
+        .expect("Error creating proof request");
+
+        let mut present = PresentCredentials::default();
+        {
+            let mut cred1 = present.add_credential(&prover_wallet.credentials[0], None, None);
+            cred1.add_requested_attribute("attr1_referent", true);
+            cred1.add_requested_predicate("predicate1_referent");
+        }
+        presentations.push(
+            prover::create_presentation_with_resolver(
+                &pres_request,
+                present,
+                None,
+                &prover_wallet.link_secret,
+                &resolver,
+            )
+            .expect("Error creating presentation"),
+        );
+        requests.push(pres_request);
+    }
+
+    // the last presentation is checked against the request for another nonce
+    let mut batch: Vec<_> = presentations.iter().zip(requests.iter()).collect();
+    batch[3].1 = &requests[0];
+
+    let results =
+        verifier::verify_presentations_batch(&batch, &resolver, &VerificationPolicy::default());
+    assert_eq!(results.len(), 4);
+    for result in &results[..3] {
+        assert!(result.as_ref().unwrap().is_valid());
+    }
+    assert_eq!(results[3].as_ref().unwrap().proof, ProofStatus::Rejected);
 }
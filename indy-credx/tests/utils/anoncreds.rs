@@ -1,4 +1,11 @@
-use indy_credx::types::{CredentialDefinitionPrivate, CredentialKeyCorrectnessProof};
+use std::collections::HashMap;
+
+use indy_credx::issuer;
+use indy_credx::prover;
+use indy_credx::types::{
+    CredentialDefinitionConfig, CredentialDefinitionId, CredentialDefinitionPrivate,
+    CredentialKeyCorrectnessProof, MakeCredentialValues, Schema, SchemaId, SignatureType,
+};
 
 use indy_data_types::anoncreds::cred_def::CredentialDefinition;
 use indy_data_types::anoncreds::credential::Credential;
@@ -52,6 +59,71 @@ impl Default for IssuerWallet {
     }
 }
 
+impl IssuerWallet {
+    /// Create a schema and a credential definition for it, which is stored in
+    /// the wallet
+    pub fn create_cred_def(
+        &mut self,
+        schema_name: &str,
+        attr_names: &[&str],
+        support_revocation: bool,
+    ) -> Schema {
+        let schema = issuer::create_schema(&self.did, schema_name, "1.0", attr_names.into(), None)
+            .expect("Error creating schema");
+        let cred_def_parts = issuer::create_credential_definition(
+            &self.did,
+            &schema,
+            "tag",
+            SignatureType::CL,
+            CredentialDefinitionConfig { support_revocation },
+        )
+        .expect("Error creating credential definition");
+        self.cred_defs.push(cred_def_parts.into());
+        schema
+    }
+
+    /// Issue a credential without revocation using the first credential
+    /// definition, and store it in the prover wallet
+    pub fn issue_credential(
+        &self,
+        schema: &Schema,
+        prover_wallet: &mut ProverWallet,
+        cred_values: MakeCredentialValues,
+    ) {
+        let cred_def = &self.cred_defs[0];
+        let cred_offer =
+            issuer::create_credential_offer(schema.id(), &cred_def.public, &cred_def.key_proof)
+                .expect("Error creating credential offer");
+        let (cred_request, cred_request_metadata) = prover::create_credential_request(
+            &prover_wallet.did,
+            &cred_def.public,
+            &prover_wallet.link_secret,
+            "default",
+            &cred_offer,
+        )
+        .expect("Error creating credential request");
+        let (mut credential, _, _) = issuer::create_credential_for_schema(
+            schema,
+            &cred_def.public,
+            &cred_def.private,
+            &cred_offer,
+            &cred_request,
+            cred_values,
+            None,
+        )
+        .expect("Error creating credential");
+        prover::process_credential(
+            &mut credential,
+            &cred_request_metadata,
+            &prover_wallet.link_secret,
+            &cred_def.public,
+            None,
+        )
+        .expect("Error processing credential");
+        prover_wallet.credentials.push(credential);
+    }
+}
+
 // A struct for keeping all issuer-related objects together
 pub struct ProverWallet {
     pub did: DidValue,
@@ -69,3 +141,30 @@ impl Default for ProverWallet {
         }
     }
 }
+
+/// Credential values using the default encoding for each raw value
+pub fn raw_values(values: &[(&str, &str)]) -> MakeCredentialValues {
+    let mut cred_values = MakeCredentialValues::default();
+    for (name, raw) in values {
+        cred_values
+            .add_raw(*name, *raw)
+            .expect("Error encoding attribute");
+    }
+    cred_values
+}
+
+/// The schema and credential definition maps used to create and verify
+/// presentations
+#[allow(clippy::type_complexity)]
+pub fn ledger<'a>(
+    schema: &'a Schema,
+    cred_def: &'a CredentialDefinition,
+) -> (
+    HashMap<SchemaId, &'a Schema>,
+    HashMap<CredentialDefinitionId, &'a CredentialDefinition>,
+) {
+    (
+        HashMap::from([(schema.id().clone(), schema)]),
+        HashMap::from([(cred_def.id().clone(), cred_def)]),
+    )
+}
@@ -41,6 +41,25 @@ pub struct CredentialDefinitionData {
     pub revocation: Option<cl_type!(CredentialRevocationPublicKey)>,
 }
 
+impl CredentialDefinitionData {
+    pub fn try_clone(&self) -> Result<Self, ConversionError> {
+        #[cfg(any(feature = "cl", feature = "cl_native"))]
+        {
+            Ok(Self {
+                primary: self.primary.try_clone().map_err(|e| e.to_string())?,
+                revocation: self.revocation.clone(),
+            })
+        }
+        #[cfg(not(any(feature = "cl", feature = "cl_native")))]
+        {
+            Ok(Self {
+                primary: self.primary.clone(),
+                revocation: self.revocation.clone(),
+            })
+        }
+    }
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(tag = "ver"))]
 pub enum CredentialDefinition {
@@ -55,6 +74,20 @@ impl CredentialDefinition {
         }
     }
 
+    pub fn try_clone(&self) -> Result<Self, ConversionError> {
+        match self {
+            CredentialDefinition::CredentialDefinitionV1(cred_def) => Ok(
+                CredentialDefinition::CredentialDefinitionV1(CredentialDefinitionV1 {
+                    id: cred_def.id.clone(),
+                    schema_id: cred_def.schema_id.clone(),
+                    signature_type: cred_def.signature_type,
+                    tag: cred_def.tag.clone(),
+                    value: cred_def.value.try_clone()?,
+                }),
+            ),
+        }
+    }
+
     pub fn to_unqualified(self) -> CredentialDefinition {
         match self {
             CredentialDefinition::CredentialDefinitionV1(cred_def) => {
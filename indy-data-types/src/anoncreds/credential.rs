@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use zeroize::Zeroize;
 
+use super::schema::attr_common_view;
 use crate::identifiers::cred_def::CredentialDefinitionId;
 use crate::identifiers::rev_reg::RevocationRegistryId;
 use crate::identifiers::schema::SchemaId;
@@ -47,6 +48,35 @@ impl Credential {
         "schema_issuer_did",
         "rev_reg_id",
     ];
+
+    /// Build the standard Indy wallet tags for the credential
+    ///
+    /// These consist of the identifier tags in `QUALIFIABLE_TAGS` along with
+    /// `schema_name` and `schema_version`, and an `attr::{name}::marker` and
+    /// `attr::{name}::value` tag for each attribute, using the normalized
+    /// attribute name.
+    pub fn tags(&self) -> HashMap<String, String> {
+        let mut tags = HashMap::with_capacity(7 + self.values.0.len() * 2);
+        tags.insert("schema_id".to_string(), self.schema_id.0.clone());
+        if let Some((_, issuer_did, name, version)) = self.schema_id.parts() {
+            tags.insert("schema_issuer_did".to_string(), issuer_did.0);
+            tags.insert("schema_name".to_string(), name);
+            tags.insert("schema_version".to_string(), version);
+        }
+        tags.insert("cred_def_id".to_string(), self.cred_def_id.0.clone());
+        if let Some(issuer_did) = self.cred_def_id.issuer_did() {
+            tags.insert("issuer_did".to_string(), issuer_did.0);
+        }
+        if let Some(rev_reg_id) = &self.rev_reg_id {
+            tags.insert("rev_reg_id".to_string(), rev_reg_id.0.clone());
+        }
+        for (name, values) in self.values.0.iter() {
+            let name = attr_common_view(name);
+            tags.insert(format!("attr::{}::marker", name), "1".to_string());
+            tags.insert(format!("attr::{}::value", name), values.raw.clone());
+        }
+        tags
+    }
 }
 
 impl Validatable for Credential {
@@ -114,3 +144,71 @@ pub struct AttributeValues {
     pub raw: String,
     pub encoded: String,
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const SCHEMA_ID: &str = "NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0";
+    pub(crate) const CRED_DEF_ID: &str =
+        "NcYxiDXkpYi6ov5FcYDi1e:3:CL:NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0:tag";
+
+    /// A credential with the given raw values and a placeholder signature
+    pub(crate) fn credential(schema_id: &str, values: &[(&str, &str)]) -> Credential {
+        let values: serde_json::Map<String, serde_json::Value> = values
+            .iter()
+            .map(|(name, raw)| (name.to_string(), json!({"raw": raw, "encoded": "1"})))
+            .collect();
+        serde_json::from_value(json!({
+            "schema_id": schema_id,
+            "cred_def_id": CRED_DEF_ID,
+            "rev_reg_id": null,
+            "values": values,
+            "signature": {
+                "p_credential": {"m_2": "1", "a": "1", "e": "1", "v": "1"},
+                "r_credential": null
+            },
+            "signature_correctness_proof": {"se": "1", "c": "1"},
+            "rev_reg": null,
+            "witness": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn credential_tags() {
+        let credential = credential(SCHEMA_ID, &[("First Name", "Alex"), ("age", "28")]);
+        let expected: HashMap<String, String> = [
+            ("schema_id", SCHEMA_ID),
+            ("schema_issuer_did", "NcYxiDXkpYi6ov5FcYDi1e"),
+            ("schema_name", "gvt"),
+            ("schema_version", "1.0"),
+            ("cred_def_id", CRED_DEF_ID),
+            ("issuer_did", "NcYxiDXkpYi6ov5FcYDi1e"),
+            ("attr::firstname::marker", "1"),
+            ("attr::firstname::value", "Alex"),
+            ("attr::age::marker", "1"),
+            ("attr::age::value", "28"),
+        ]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        assert_eq!(credential.tags(), expected);
+    }
+
+    #[test]
+    fn credential_tags_with_revocation_registry() {
+        let mut credential = credential(SCHEMA_ID, &[("name", "Alex")]);
+        let rev_reg_id = format!("NcYxiDXkpYi6ov5FcYDi1e:4:{}:CL_ACCUM:tag", CRED_DEF_ID);
+        credential.rev_reg_id = Some(RevocationRegistryId(rev_reg_id.clone()));
+        assert_eq!(credential.tags().get("rev_reg_id"), Some(&rev_reg_id));
+    }
+
+    #[test]
+    fn credential_tags_for_unparsed_schema_id() {
+        let tags = credential("schema", &[("name", "Alex")]).tags();
+        assert_eq!(tags.get("schema_id").map(String::as_str), Some("schema"));
+        assert!(!tags.contains_key("schema_name"));
+        assert!(!tags.contains_key("schema_issuer_did"));
+    }
+}
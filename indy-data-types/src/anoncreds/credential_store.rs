@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::credential::Credential;
+use super::wql::Query;
+
+#[derive(Debug)]
+struct StoredCredential {
+    credential: Credential,
+    tags: HashMap<String, String>,
+}
+
+/// An in-memory collection of credentials which may be searched using WQL
+/// queries over the standard Indy credential tags
+#[derive(Debug, Default)]
+pub struct CredentialStore {
+    entries: BTreeMap<String, StoredCredential>,
+}
+
+impl CredentialStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a credential under the given identifier, returning any credential
+    /// previously stored with the same identifier
+    pub fn insert(&mut self, id: impl Into<String>, credential: Credential) -> Option<Credential> {
+        let tags = credential.tags();
+        self.entries
+            .insert(id.into(), StoredCredential { credential, tags })
+            .map(|prev| prev.credential)
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<Credential> {
+        self.entries.remove(id).map(|entry| entry.credential)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Credential> {
+        self.entries.get(id).map(|entry| &entry.credential)
+    }
+
+    /// Get the tags recorded for a stored credential
+    pub fn tags(&self, id: &str) -> Option<&HashMap<String, String>> {
+        self.entries.get(id).map(|entry| &entry.tags)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over all stored credentials in identifier order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Credential)> {
+        self.entries
+            .iter()
+            .map(|(id, entry)| (id.as_str(), &entry.credential))
+    }
+
+    /// Find the stored credentials matching a query, in identifier order
+    pub fn search<'s>(
+        &'s self,
+        query: &'s Query,
+    ) -> impl Iterator<Item = (&'s str, &'s Credential)> + 's {
+        self.entries
+            .iter()
+            .filter(move |(_, entry)| query.matches(&entry.tags))
+            .map(|(id, entry)| (id.as_str(), &entry.credential))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anoncreds::credential::tests::{credential, SCHEMA_ID};
+
+    fn store() -> CredentialStore {
+        let mut store = CredentialStore::new();
+        store.insert(
+            "b",
+            credential(SCHEMA_ID, &[("name", "Sam"), ("age", "35")]),
+        );
+        store.insert(
+            "a",
+            credential(SCHEMA_ID, &[("name", "Alex"), ("age", "28")]),
+        );
+        store.insert(
+            "c",
+            credential("NcYxiDXkpYi6ov5FcYDi1e:2:xyz:1.10", &[("name", "Alex")]),
+        );
+        store
+    }
+
+    fn search(store: &CredentialStore, query: serde_json::Value) -> Vec<String> {
+        let query: Query = serde_json::from_value(query).unwrap();
+        store.search(&query).map(|(id, _)| id.to_string()).collect()
+    }
+
+    #[test]
+    fn credential_store_insert_and_remove() {
+        let mut store = store();
+        assert_eq!(store.len(), 3);
+        assert_eq!(
+            store.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            ["a", "b", "c"]
+        );
+        assert_eq!(
+            store
+                .tags("a")
+                .unwrap()
+                .get("attr::name::value")
+                .map(String::as_str),
+            Some("Alex")
+        );
+
+        let prev = store.insert("a", credential(SCHEMA_ID, &[("name", "Bob")]));
+        assert_eq!(prev.unwrap().values.0["name"].raw, "Alex");
+        assert_eq!(store.len(), 3);
+        assert_eq!(
+            store
+                .tags("a")
+                .unwrap()
+                .get("attr::name::value")
+                .map(String::as_str),
+            Some("Bob")
+        );
+        assert!(!store.tags("a").unwrap().contains_key("attr::age::value"));
+
+        assert!(store.remove("a").is_some());
+        assert!(store.remove("a").is_none());
+        assert!(store.get("a").is_none());
+        assert!(store.tags("a").is_none());
+        assert_eq!(store.len(), 2);
+        assert!(!store.is_empty());
+        assert!(CredentialStore::new().is_empty());
+    }
+
+    #[test]
+    fn credential_store_search() {
+        let store = store();
+        assert_eq!(search(&store, json!({})), ["a", "b", "c"]);
+        assert_eq!(search(&store, json!({"schema_name": "gvt"})), ["a", "b"]);
+        assert_eq!(
+            search(&store, json!({"attr::name::value": "Alex"})),
+            ["a", "c"]
+        );
+        assert_eq!(
+            search(&store, json!({"attr::age::value": {"$gt": "30"}})),
+            ["b"]
+        );
+        assert_eq!(
+            search(&store, json!({"schema_version": {"$gt": "1.9"}})),
+            ["c"]
+        );
+        assert_eq!(
+            search(&store, json!({"attr::age::marker": "1"})),
+            ["a", "b"]
+        );
+        let empty_or = Query::Or(vec![]);
+        assert_eq!(store.search(&empty_or).count(), 0);
+    }
+}
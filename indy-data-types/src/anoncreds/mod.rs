@@ -13,6 +13,9 @@ pub mod cred_request;
 /// Credentials
 pub mod credential;
 
+/// In-memory credential storage
+pub mod credential_store;
+
 /// Identity link secret
 #[cfg(any(feature = "cl", feature = "cl_native"))]
 pub mod link_secret;
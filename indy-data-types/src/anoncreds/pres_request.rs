@@ -14,7 +14,7 @@ use crate::identifiers::cred_def::CredentialDefinitionId;
 use crate::identifiers::rev_reg::RevocationRegistryId;
 use crate::identifiers::schema::SchemaId;
 use crate::qualifiable::{self, Qualifiable};
-use crate::{invalid, Validatable, ValidationError};
+use crate::{Validatable, ValidationError};
 
 #[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
@@ -427,6 +427,52 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "serde")]
+    mod restrictions {
+        use super::*;
+
+        fn request(restrictions: Value) -> PresentationRequest {
+            serde_json::from_value(json!({
+                "nonce": "123456",
+                "name": "name",
+                "version": "1.0",
+                "requested_attributes": {
+                    "attr1_referent": {"name": "name", "restrictions": restrictions}
+                },
+                "requested_predicates": {},
+            }))
+            .unwrap()
+        }
+
+        #[test]
+        fn presentation_request_accepts_all_operators() {
+            request(json!({"$and": [
+                {"schema_name": "gvt"},
+                {"schema_version": {"$neq": "1.0"}},
+                {"schema_version": {"$gt": "1.0"}},
+                {"schema_version": {"$gte": "1.2"}},
+                {"schema_version": {"$lt": "3.0"}},
+                {"schema_version": {"$lte": "2.0"}},
+                {"cred_def_id": {"$like": "NcYxiDXkpYi6ov5FcYDi1e:3:CL:%"}},
+                {"issuer_did": {"$in": ["NcYxiDXkpYi6ov5FcYDi1e"]}},
+                {"$exist": ["attr::name::value"]},
+                {"$not": {"schema_name": "other"}},
+                {"$or": [{"schema_name": "gvt"}]}
+            ]}))
+            .validate()
+            .unwrap();
+        }
+
+        #[test]
+        fn presentation_request_v1_rejects_qualified_restrictions() {
+            request(json!({
+                "cred_def_id": {"$like": "creddef:sov:did:sov:NcYxiDXkpYi6ov5FcYDi1e:3:CL:%"}
+            }))
+            .validate()
+            .unwrap_err();
+        }
+    }
+
     mod to_unqualified {
         use super::*;
 
@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 use crate::identifiers::cred_def::CredentialDefinitionId;
 use crate::identifiers::rev_reg::RevocationRegistryId;
-use crate::{invalid, ConversionError, Qualifiable, Validatable, ValidationError};
+use crate::{ConversionError, Qualifiable, Validatable, ValidationError};
 
 pub const CL_ACCUM: &str = "CL_ACCUM";
 
@@ -1,11 +1,19 @@
 use crate::identifiers::schema::SchemaId;
 use crate::{Qualifiable, Validatable, ValidationError};
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
 
 pub const MAX_ATTRIBUTES_COUNT: usize = 125;
 
+/// The attribute name reserved for the link secret of the prover.
+pub const LINK_SECRET_ATTRIBUTE: &str = "master_secret";
+
+/// Normalize an attribute name for comparison, ignoring spaces and case.
+pub fn attr_common_view(attr: &str) -> String {
+    attr.replace(' ', "").to_lowercase()
+}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde", serde(tag = "ver"))]
@@ -130,6 +138,30 @@ impl Validatable for AttributeNames {
             )
             .into());
         }
+
+        let mut names: Vec<&String> = self.0.iter().collect();
+        names.sort();
+        let mut seen = HashMap::with_capacity(names.len());
+        for name in names {
+            let common = attr_common_view(name);
+            if common.is_empty() {
+                return Err("Schema attribute names cannot be empty".into());
+            }
+            if common == LINK_SECRET_ATTRIBUTE {
+                return Err(format!(
+                    "Schema attribute name {:?} is reserved for the link secret",
+                    name
+                )
+                .into());
+            }
+            if let Some(prev) = seen.insert(common, name) {
+                return Err(format!(
+                    "Schema attribute names {:?} and {:?} are equivalent",
+                    prev, name
+                )
+                .into());
+            }
+        }
         Ok(())
     }
 }
@@ -191,4 +223,23 @@ mod test_schema_validation {
         let schema: SchemaV1 = serde_json::from_str(&schema_json).unwrap();
         schema.validate().unwrap_err();
     }
+
+    #[test]
+    fn test_attribute_names_validation() {
+        AttributeNames::from(&["First Name", "age"][..])
+            .validate()
+            .unwrap();
+        for attrs in [
+            &["First Name", "firstname"][..],
+            &["name", "Master_Secret"][..],
+            &["name", " "][..],
+            &[][..],
+        ] {
+            AttributeNames::from(attrs).validate().unwrap_err();
+        }
+        let many: Vec<String> = (0..=MAX_ATTRIBUTES_COUNT)
+            .map(|idx| format!("attr{}", idx))
+            .collect();
+        AttributeNames::from(many).validate().unwrap_err();
+    }
 }
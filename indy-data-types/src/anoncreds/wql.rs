@@ -1,4 +1,10 @@
-//! Indy WQL (wallet query language) parsing and optimization
+//! Indy WQL (wallet query language) parsing, optimization, evaluation and SQL translation
+
+mod eval;
+mod sql;
+
+pub use self::eval::{compare_values, like_matches};
+pub use self::sql::{SqlPlaceholder, SqlQuery, SqlTagLayout};
 
 /// An abstract query representation over a key and value type
 #[derive(Debug, Hash, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -169,7 +175,7 @@ impl<K, V> Default for AbstractQuery<K, V> {
 
 #[cfg(feature = "serde_support")]
 mod serde_support {
-    use std::string;
+    use std::fmt;
 
     use serde::ser::{Serialize, Serializer};
     use serde::{de, Deserialize, Deserializer};
@@ -269,9 +275,9 @@ mod serde_support {
         }
     }
 
-    impl string::ToString for Query {
-        fn to_string(&self) -> String {
-            self.to_value().to_string()
+    impl fmt::Display for Query {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.to_value())
         }
     }
 
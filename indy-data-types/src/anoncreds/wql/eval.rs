@@ -0,0 +1,191 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use super::AbstractQuery;
+
+impl<K, V> AbstractQuery<K, V>
+where
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    /// Evaluate the query against a set of tag names and values
+    ///
+    /// Comparison operators require the tag to be present. Values are compared
+    /// as integers when both are integers, segment by segment when both are
+    /// dot-separated numbers such as versions, and otherwise as strings.
+    /// `$like` patterns support the `%` and `_` wildcards and are case-sensitive.
+    /// An empty `$and` places no restriction on the tags, while an empty `$or`
+    /// matches no tags, as in the restrictions checked by the verifier. Note
+    /// that an empty `$or` in JSON is parsed as an empty `$and`.
+    pub fn matches(&self, tags: &HashMap<String, String>) -> bool {
+        let tag = |name: &K| tags.get(name.as_ref()).map(String::as_str);
+        let compare = |name: &K, value: &V| {
+            tag(name).map(|tag_value| compare_values(tag_value, value.as_ref()))
+        };
+        match self {
+            Self::Eq(name, value) => tag(name) == Some(value.as_ref()),
+            Self::Neq(name, value) => {
+                tag(name).is_some_and(|tag_value| tag_value != value.as_ref())
+            }
+            Self::Gt(name, value) => compare(name, value) == Some(Ordering::Greater),
+            Self::Gte(name, value) => {
+                matches!(
+                    compare(name, value),
+                    Some(Ordering::Greater | Ordering::Equal)
+                )
+            }
+            Self::Lt(name, value) => compare(name, value) == Some(Ordering::Less),
+            Self::Lte(name, value) => {
+                matches!(compare(name, value), Some(Ordering::Less | Ordering::Equal))
+            }
+            Self::Like(name, pattern) => {
+                tag(name).is_some_and(|tag_value| like_matches(tag_value, pattern.as_ref()))
+            }
+            Self::In(name, values) => tag(name)
+                .is_some_and(|tag_value| values.iter().any(|value| value.as_ref() == tag_value)),
+            Self::Exist(names) => names.iter().all(|name| tag(name).is_some()),
+            Self::And(queries) => queries.iter().all(|query| query.matches(tags)),
+            Self::Or(queries) => queries.iter().any(|query| query.matches(tags)),
+            Self::Not(query) => !query.matches(tags),
+        }
+    }
+}
+
+/// Compare two tag values, treating integers and dot-separated numbers numerically
+pub fn compare_values(left: &str, right: &str) -> Ordering {
+    if let (Ok(left), Ok(right)) = (left.parse::<i64>(), right.parse::<i64>()) {
+        return left.cmp(&right);
+    }
+    if let (Some(left), Some(right)) = (version_segments(left), version_segments(right)) {
+        return left.cmp(&right);
+    }
+    left.cmp(right)
+}
+
+fn version_segments(value: &str) -> Option<Vec<u64>> {
+    if !value.contains('.') {
+        return None;
+    }
+    value
+        .split('.')
+        .map(|segment| {
+            if !segment.is_empty() && segment.bytes().all(|c| c.is_ascii_digit()) {
+                segment.parse().ok()
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Match a value against an SQL `LIKE` pattern, where `%` matches any sequence
+/// of characters and `_` matches a single character
+pub fn like_matches(value: &str, pattern: &str) -> bool {
+    let value: Vec<char> = value.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    // matched[j] is true when the value prefix matches the first j pattern characters
+    let mut matched = vec![false; pattern.len() + 1];
+    matched[0] = true;
+    for j in 0..pattern.len() {
+        matched[j + 1] = matched[j] && pattern[j] == '%';
+    }
+    for c in value {
+        let mut next = vec![false; pattern.len() + 1];
+        for (j, p) in pattern.iter().enumerate() {
+            next[j + 1] = match p {
+                '%' => next[j] || matched[j + 1],
+                '_' => matched[j],
+                p => matched[j] && *p == c,
+            };
+        }
+        matched = next;
+    }
+    matched[pattern.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anoncreds::wql::Query;
+
+    fn tags() -> HashMap<String, String> {
+        [
+            ("schema_name", "gvt"),
+            ("schema_version", "1.10"),
+            ("attr::age::value", "28"),
+            ("attr::name::value", "Alex"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    fn eval(query: serde_json::Value) -> bool {
+        let query: Query = serde_json::from_value(query).unwrap();
+        query.matches(&tags())
+    }
+
+    #[test]
+    fn test_eval_operators() {
+        assert!(eval(serde_json::json!({})));
+        assert!(eval(serde_json::json!({"schema_name": "gvt"})));
+        assert!(!eval(serde_json::json!({"schema_name": "xyz"})));
+        assert!(eval(serde_json::json!({"schema_name": {"$neq": "xyz"}})));
+        assert!(!eval(serde_json::json!({"missing": {"$neq": "xyz"}})));
+        assert!(eval(serde_json::json!({"attr::age::value": {"$gt": "9"}})));
+        assert!(eval(
+            serde_json::json!({"attr::age::value": {"$gte": "28"}})
+        ));
+        assert!(!eval(
+            serde_json::json!({"attr::age::value": {"$lt": "28"}})
+        ));
+        assert!(eval(
+            serde_json::json!({"attr::age::value": {"$lte": "100"}})
+        ));
+        assert!(eval(serde_json::json!({"schema_version": {"$gt": "1.9"}})));
+        assert!(eval(
+            serde_json::json!({"attr::name::value": {"$lt": "Bob"}})
+        ));
+        assert!(eval(
+            serde_json::json!({"attr::name::value": {"$like": "A%x"}})
+        ));
+        assert!(eval(
+            serde_json::json!({"attr::name::value": {"$like": "_le_"}})
+        ));
+        assert!(!eval(
+            serde_json::json!({"attr::name::value": {"$like": "a%"}})
+        ));
+        assert!(eval(
+            serde_json::json!({"schema_name": {"$in": ["abc", "gvt"]}})
+        ));
+        assert!(eval(
+            serde_json::json!({"$exist": ["schema_name", "attr::age::value"]})
+        ));
+        assert!(!eval(
+            serde_json::json!({"$exist": ["schema_name", "missing"]})
+        ));
+        assert!(eval(
+            serde_json::json!({"$or": [{"schema_name": "xyz"}, {"schema_version": "1.10"}]})
+        ));
+        assert!(!eval(
+            serde_json::json!({"$and": [{"schema_name": "gvt"}, {"schema_version": "1.9"}]})
+        ));
+        assert!(eval(serde_json::json!({"$not": {"schema_name": "xyz"}})));
+        assert!(Query::And(vec![]).matches(&tags()));
+        assert!(!Query::Or(vec![]).matches(&tags()));
+        assert!(Query::Or(vec![Query::And(vec![]), Query::Or(vec![])]).matches(&tags()));
+        assert!(!Query::Not(Box::new(Query::And(vec![]))).matches(&tags()));
+    }
+
+    #[test]
+    fn test_like_matches() {
+        assert!(like_matches("", ""));
+        assert!(like_matches("", "%"));
+        assert!(!like_matches("", "_"));
+        assert!(like_matches("abc", "a%"));
+        assert!(like_matches("abc", "%b%"));
+        assert!(like_matches("abc", "a_c"));
+        assert!(!like_matches("abc", "a_"));
+        assert!(like_matches("a%c", "a%%c"));
+    }
+}
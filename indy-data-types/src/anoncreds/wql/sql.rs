@@ -0,0 +1,608 @@
+use std::fmt::Write;
+
+use super::AbstractQuery;
+use crate::ConversionError;
+
+/// The placeholder syntax used for bound parameters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SqlPlaceholder {
+    /// Positional `?` placeholders
+    Question,
+    /// Numbered `$n` placeholders starting from `first`
+    Numbered { first: usize },
+}
+
+/// The layout of a table holding the tags for stored items
+///
+/// Each row of the tag table holds one tag name and value for an item. When
+/// `plaintext_column` is set, tag names and values may be stored encrypted,
+/// and the column holds 1 for plaintext tags and 0 for encrypted tags.
+/// Otherwise all tags are stored as plaintext.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SqlTagLayout {
+    /// The item identifier expression in the enclosing query
+    pub item_id: String,
+    pub tag_table: String,
+    /// The column of the tag table referencing the item identifier
+    pub tag_item_id: String,
+    pub name_column: String,
+    pub value_column: String,
+    pub plaintext_column: Option<String>,
+    pub placeholder: SqlPlaceholder,
+}
+
+impl SqlTagLayout {
+    /// A layout where all tag names and values are stored as plaintext
+    pub fn plaintext(item_id: impl Into<String>, tag_table: impl Into<String>) -> Self {
+        Self {
+            item_id: item_id.into(),
+            tag_table: tag_table.into(),
+            tag_item_id: "item_id".to_string(),
+            name_column: "name".to_string(),
+            value_column: "value".to_string(),
+            plaintext_column: None,
+            placeholder: SqlPlaceholder::Question,
+        }
+    }
+
+    /// A layout where tags may be stored encrypted, as indicated by the
+    /// `plaintext` column
+    pub fn encrypted(item_id: impl Into<String>, tag_table: impl Into<String>) -> Self {
+        Self {
+            plaintext_column: Some("plaintext".to_string()),
+            ..Self::plaintext(item_id, tag_table)
+        }
+    }
+
+    pub fn with_placeholder(mut self, placeholder: SqlPlaceholder) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+}
+
+/// A SQL `WHERE` clause fragment and its bound parameters
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SqlQuery<P> {
+    pub clause: String,
+    pub params: Vec<P>,
+}
+
+impl<K, V> AbstractQuery<K, V> {
+    /// Translate the query into a SQL condition on the item identifier
+    ///
+    /// The query is simplified first, and `None` is returned when it places no
+    /// restriction on the items. As with `matches`, an empty `$or` matches no
+    /// items. In an encrypted layout, `is_plaintext`
+    /// classifies the tag names and only plaintext tags may be used with the
+    /// comparison and `$like` operators. Encrypted tag names and values are
+    /// expected to be encrypted by the caller, for instance using `map`.
+    ///
+    /// The generated SQL is written for and tested with SQLite only.
+    ///
+    /// Comparisons follow the rule used by `matches` for integers: when both
+    /// the stored value and the operand are integers they are compared
+    /// numerically, and otherwise as strings according to the collation rules
+    /// of the database. This differs from `matches` for dot-separated numbers
+    /// such as versions, which are compared as strings so that `"1.10"` is
+    /// less than `"1.9"`, and for integers outside the 64-bit range, which
+    /// SQLite saturates rather than comparing as strings. `$like` is performed
+    /// by the database on the stored values.
+    pub fn to_sql<P>(
+        self,
+        layout: &SqlTagLayout,
+        is_plaintext: impl Fn(&K) -> bool,
+    ) -> Result<Option<SqlQuery<P>>, ConversionError>
+    where
+        K: Into<P>,
+        V: Into<P>,
+    {
+        let query = match simplify(self) {
+            Some(query) => query,
+            None => return Ok(None),
+        };
+        let mut builder = SqlBuilder {
+            layout,
+            is_plaintext: &is_plaintext,
+            clause: String::new(),
+            params: Vec::new(),
+        };
+        builder.push_query(query)?;
+        Ok(Some(SqlQuery {
+            clause: builder.clause,
+            params: builder.params,
+        }))
+    }
+}
+
+/// Simplify a query as `AbstractQuery::optimise` does, returning `None` when
+/// it places no restriction on the items. Unlike `optimise`, an empty `$or`
+/// is kept as a query matching no items, and a `$not` of an unrestricted
+/// query becomes one.
+fn simplify<K, V>(query: AbstractQuery<K, V>) -> Option<AbstractQuery<K, V>> {
+    match query {
+        AbstractQuery::Not(query) => match simplify(*query) {
+            None => Some(AbstractQuery::Or(vec![])),
+            Some(AbstractQuery::Not(query)) => Some(*query),
+            Some(query) => Some(AbstractQuery::Not(Box::new(query))),
+        },
+        AbstractQuery::And(queries) => {
+            let mut queries: Vec<_> = queries.into_iter().filter_map(simplify).collect();
+            match queries.len() {
+                0 => None,
+                1 => Some(queries.remove(0)),
+                _ => Some(AbstractQuery::And(queries)),
+            }
+        }
+        AbstractQuery::Or(queries) => {
+            // an unrestricted alternative matches every item
+            let mut queries = queries
+                .into_iter()
+                .map(simplify)
+                .collect::<Option<Vec<_>>>()?;
+            match queries.len() {
+                1 => Some(queries.remove(0)),
+                _ => Some(AbstractQuery::Or(queries)),
+            }
+        }
+        query => query.optimise(),
+    }
+}
+
+struct SqlBuilder<'a, K, P> {
+    layout: &'a SqlTagLayout,
+    is_plaintext: &'a dyn Fn(&K) -> bool,
+    clause: String,
+    params: Vec<P>,
+}
+
+impl<'a, K, P> SqlBuilder<'a, K, P>
+where
+    K: Into<P>,
+{
+    fn push_query<V>(&mut self, query: AbstractQuery<K, V>) -> Result<(), ConversionError>
+    where
+        V: Into<P>,
+    {
+        match query {
+            AbstractQuery::Eq(name, value) => self.push_tag(name, "=", vec![value], false),
+            AbstractQuery::Neq(name, value) => self.push_tag(name, "!=", vec![value], false),
+            AbstractQuery::Gt(name, value) => self.push_comparison(name, ">", value),
+            AbstractQuery::Gte(name, value) => self.push_comparison(name, ">=", value),
+            AbstractQuery::Lt(name, value) => self.push_comparison(name, "<", value),
+            AbstractQuery::Lte(name, value) => self.push_comparison(name, "<=", value),
+            AbstractQuery::Like(name, value) => self.push_tag(name, "LIKE", vec![value], true),
+            AbstractQuery::In(_, values) if values.is_empty() => {
+                self.clause.push_str("1 = 0");
+                Ok(())
+            }
+            AbstractQuery::In(name, values) => self.push_tag(name, "IN", values, false),
+            AbstractQuery::Exist(names) if names.is_empty() => {
+                self.clause.push_str("1 = 1");
+                Ok(())
+            }
+            AbstractQuery::Exist(names) => self.push_list(names, " AND ", |builder, name| {
+                builder.push_tag::<V>(name, "", vec![], false)
+            }),
+            AbstractQuery::And(queries) if queries.is_empty() => {
+                self.clause.push_str("1 = 1");
+                Ok(())
+            }
+            AbstractQuery::And(queries) => {
+                self.push_list(queries, " AND ", |builder, query| builder.push_query(query))
+            }
+            AbstractQuery::Or(queries) if queries.is_empty() => {
+                self.clause.push_str("1 = 0");
+                Ok(())
+            }
+            AbstractQuery::Or(queries) => {
+                self.push_list(queries, " OR ", |builder, query| builder.push_query(query))
+            }
+            AbstractQuery::Not(query) => {
+                self.clause.push_str("NOT ");
+                self.push_list(vec![*query], "", |builder, query| builder.push_query(query))
+            }
+        }
+    }
+
+    fn push_list<T>(
+        &mut self,
+        items: Vec<T>,
+        separator: &str,
+        mut f: impl FnMut(&mut Self, T) -> Result<(), ConversionError>,
+    ) -> Result<(), ConversionError> {
+        self.clause.push('(');
+        for (idx, item) in items.into_iter().enumerate() {
+            if idx > 0 {
+                self.clause.push_str(separator);
+            }
+            f(self, item)?;
+        }
+        self.clause.push(')');
+        Ok(())
+    }
+
+    /// Add a condition that the item has a tag with the given name and a value
+    /// matching the operator. An empty operator only checks the tag name.
+    fn push_tag<V>(
+        &mut self,
+        name: K,
+        op: &str,
+        values: Vec<V>,
+        requires_plaintext: bool,
+    ) -> Result<(), ConversionError>
+    where
+        V: Into<P>,
+    {
+        let plaintext = match &self.layout.plaintext_column {
+            Some(column) => Some((column, (self.is_plaintext)(&name))),
+            None => None,
+        };
+        if requires_plaintext && plaintext.is_some_and(|(_, plaintext)| !plaintext) {
+            return Err(format!("The {} operator is not supported for encrypted tags", op).into());
+        }
+
+        let layout = self.layout;
+        write!(
+            self.clause,
+            "{} IN (SELECT {} FROM {} WHERE {} = ",
+            layout.item_id, layout.tag_item_id, layout.tag_table, layout.name_column
+        )
+        .unwrap();
+        self.push_param(name.into());
+        if !op.is_empty() {
+            write!(self.clause, " AND {} {} ", layout.value_column, op).unwrap();
+            if op == "IN" {
+                self.push_list(values, ", ", |builder, value| {
+                    builder.push_param(value.into());
+                    Ok(())
+                })?;
+            } else {
+                for value in values {
+                    self.push_param(value.into());
+                }
+            }
+        }
+        if let Some((column, plaintext)) = plaintext {
+            write!(self.clause, " AND {} = {}", column, plaintext as u8).unwrap();
+        }
+        self.clause.push(')');
+        Ok(())
+    }
+
+    /// Add a condition comparing the value of a plaintext tag with an operand.
+    /// The operand is bound once and compared numerically when both it and the
+    /// stored value are integers.
+    fn push_comparison<V>(&mut self, name: K, op: &str, value: V) -> Result<(), ConversionError>
+    where
+        V: Into<P>,
+    {
+        let layout = self.layout;
+        let plaintext_column = match &layout.plaintext_column {
+            Some(_) if !(self.is_plaintext)(&name) => {
+                return Err(
+                    format!("The {} operator is not supported for encrypted tags", op).into(),
+                );
+            }
+            column => column,
+        };
+        write!(
+            self.clause,
+            "{} IN (SELECT {} FROM {}, (SELECT CAST(",
+            layout.item_id, layout.tag_item_id, layout.tag_table
+        )
+        .unwrap();
+        self.push_param(value.into());
+        write!(
+            self.clause,
+            " AS TEXT) AS operand) AS o WHERE {} = ",
+            layout.name_column
+        )
+        .unwrap();
+        self.push_param(name.into());
+        let value = &layout.value_column;
+        write!(
+            self.clause,
+            " AND CASE WHEN {} AND {} THEN CAST({} AS BIGINT) {op} CAST(o.operand AS BIGINT) \
+            ELSE {} {op} o.operand END",
+            integer_test(value),
+            integer_test("o.operand"),
+            value,
+            value,
+            op = op
+        )
+        .unwrap();
+        if let Some(column) = plaintext_column {
+            write!(self.clause, " AND {} = 1", column).unwrap();
+        }
+        self.clause.push(')');
+        Ok(())
+    }
+
+    fn push_param(&mut self, param: P) {
+        self.params.push(param);
+        match self.layout.placeholder {
+            SqlPlaceholder::Question => self.clause.push('?'),
+            SqlPlaceholder::Numbered { first } => {
+                write!(self.clause, "${}", first + self.params.len() - 1).unwrap()
+            }
+        }
+    }
+}
+
+/// A condition that the expression holds an optionally signed integer, as
+/// accepted by `compare_values`
+fn integer_test(expr: &str) -> String {
+    format!(
+        "(LTRIM(SUBSTR({0}, 2), '0123456789') = '' \
+        AND LTRIM(SUBSTR({0}, 1, 1), '+-0123456789') = '' \
+        AND {0} NOT IN ('', '+', '-'))",
+        expr
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rusqlite::{params_from_iter, types::Value, Connection};
+
+    use super::*;
+    use crate::anoncreds::wql::Query;
+
+    const ITEMS: &[&[(&str, &str)]] = &[
+        &[("schema_name", "gvt"), ("~age", "28"), ("name", "Alex")],
+        &[("schema_name", "gvt"), ("~age", "35"), ("name", "Sam")],
+        &[("schema_name", "xyz"), ("~age", "19")],
+    ];
+
+    fn is_plaintext(name: &str) -> bool {
+        name.starts_with('~')
+    }
+
+    // A stand-in for deterministic tag encryption
+    fn encrypt(value: &str) -> Vec<u8> {
+        value.bytes().rev().collect()
+    }
+
+    enum TagName {
+        Plain(String),
+        Encrypted(Vec<u8>),
+    }
+
+    impl From<TagName> for Value {
+        fn from(name: TagName) -> Value {
+            match name {
+                TagName::Plain(name) => Value::Text(name),
+                TagName::Encrypted(name) => Value::Blob(name),
+            }
+        }
+    }
+
+    fn open_db(encrypted: bool) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY);
+            CREATE TABLE items_tags (item_id INTEGER, name BLOB, value BLOB, plaintext INTEGER);",
+        )
+        .unwrap();
+        for (idx, tags) in ITEMS.iter().enumerate() {
+            conn.execute("INSERT INTO items (id) VALUES (?1)", [idx as i64])
+                .unwrap();
+            for (name, value) in tags.iter() {
+                let (name, value, plaintext) = if encrypted && !is_plaintext(name) {
+                    (
+                        Value::Blob(encrypt(name)),
+                        Value::Blob(encrypt(value)),
+                        false,
+                    )
+                } else {
+                    (
+                        Value::from(name.to_string()),
+                        Value::from(value.to_string()),
+                        true,
+                    )
+                };
+                conn.execute(
+                    "INSERT INTO items_tags (item_id, name, value, plaintext) VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![idx as i64, name, value, plaintext],
+                )
+                .unwrap();
+            }
+        }
+        conn
+    }
+
+    fn select(conn: &Connection, query: Option<SqlQuery<Value>>) -> Vec<usize> {
+        let (clause, params) = match query {
+            Some(query) => (query.clause, query.params),
+            None => ("1 = 1".to_string(), vec![]),
+        };
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT i.id FROM items i WHERE {} ORDER BY i.id",
+                clause
+            ))
+            .unwrap();
+        let rows = stmt
+            .query_map(params_from_iter(params), |row| row.get::<_, i64>(0))
+            .unwrap();
+        rows.map(|id| id.unwrap() as usize).collect()
+    }
+
+    fn evaluate(query: &Query) -> Vec<usize> {
+        ITEMS
+            .iter()
+            .enumerate()
+            .filter(|(_, tags)| {
+                let tags: HashMap<String, String> = tags
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect();
+                query.matches(&tags)
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    fn queries() -> Vec<Query> {
+        [
+            json!({}),
+            json!({"schema_name": "gvt"}),
+            json!({"schema_name": {"$neq": "gvt"}}),
+            json!({"name": {"$neq": "Alex"}}),
+            json!({"schema_name": {"$in": ["xyz", "abc"]}}),
+            json!({"schema_name": {"$in": []}}),
+            json!({"$exist": ["name", "~age"]}),
+            json!({"~age": {"$gt": "28"}}),
+            json!({"~age": {"$gte": "28"}}),
+            json!({"~age": {"$lt": "28"}}),
+            json!({"~age": {"$lte": "28"}}),
+            json!({"~age": {"$like": "_9"}}),
+            json!({"$or": [{"name": "Sam"}, {"schema_name": "xyz"}]}),
+            json!({"$and": [{"schema_name": "gvt"}, {"$not": {"name": "Sam"}}]}),
+            json!({"$not": {"$exist": ["name"]}}),
+        ]
+        .into_iter()
+        .map(|query| serde_json::from_value(query).unwrap())
+        .chain([
+            Query::Or(vec![]),
+            Query::And(vec![
+                Query::Eq("name".into(), "Sam".into()),
+                Query::Or(vec![]),
+            ]),
+            Query::Or(vec![
+                Query::And(vec![]),
+                Query::Eq("name".into(), "Sam".into()),
+            ]),
+            Query::Not(Box::new(Query::And(vec![]))),
+        ])
+        .collect()
+    }
+
+    #[test]
+    fn test_sql_plaintext_layout() {
+        let conn = open_db(false);
+        let layout = SqlTagLayout::plaintext("i.id", "items_tags");
+        for query in queries() {
+            let expected = evaluate(&query);
+            let sql = query.clone().to_sql::<Value>(&layout, |_| true).unwrap();
+            assert_eq!(select(&conn, sql), expected, "query: {:?}", query);
+        }
+    }
+
+    #[test]
+    fn test_sql_encrypted_layout() {
+        let conn = open_db(true);
+        let layout = SqlTagLayout::encrypted("i.id", "items_tags")
+            .with_placeholder(SqlPlaceholder::Numbered { first: 1 });
+        for query in queries() {
+            let expected = evaluate(&query);
+            let encrypted = query
+                .clone()
+                .map::<_, _, _, _, ConversionError>(
+                    &mut |name| {
+                        Ok(if is_plaintext(&name) {
+                            TagName::Plain(name)
+                        } else {
+                            TagName::Encrypted(encrypt(&name))
+                        })
+                    },
+                    &mut |name, value| {
+                        Ok(if is_plaintext(name) {
+                            Value::Text(value)
+                        } else {
+                            Value::Blob(encrypt(&value))
+                        })
+                    },
+                )
+                .unwrap();
+            let sql = encrypted
+                .to_sql::<Value>(&layout, |name| matches!(name, TagName::Plain(_)))
+                .unwrap();
+            assert_eq!(select(&conn, sql), expected, "query: {:?}", query);
+        }
+
+        let query: Query = serde_json::from_value(json!({"name": {"$gt": "A"}})).unwrap();
+        assert!(query
+            .map_names::<_, ConversionError>(|name| Ok(TagName::Encrypted(encrypt(&name))))
+            .unwrap()
+            .to_sql::<Value>(&layout, |name| matches!(name, TagName::Plain(_)))
+            .is_err());
+    }
+
+    #[test]
+    fn test_sql_integer_comparisons() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY);
+            CREATE TABLE items_tags (item_id INTEGER, name BLOB, value BLOB, plaintext INTEGER);",
+        )
+        .unwrap();
+        let values = ["9", "10", "100", "-5", "+7", "abc", "1.10"];
+        let items: Vec<HashMap<String, String>> = values
+            .iter()
+            .map(|value| [("~num".to_string(), value.to_string())].into())
+            .collect();
+        for (idx, value) in values.iter().enumerate() {
+            conn.execute("INSERT INTO items (id) VALUES (?1)", [idx as i64])
+                .unwrap();
+            conn.execute(
+                "INSERT INTO items_tags (item_id, name, value, plaintext) VALUES (?1, '~num', ?2, 1)",
+                rusqlite::params![idx as i64, value],
+            )
+            .unwrap();
+        }
+        let layout = SqlTagLayout::encrypted("i.id", "items_tags");
+        for (op, operand, expected) in [
+            ("$gt", "9", vec![1, 2, 5]),
+            ("$gte", "10", vec![1, 2, 5]),
+            ("$lt", "10", vec![0, 3, 4, 6]),
+            ("$lte", "-5", vec![3]),
+            ("$gt", "+8", vec![0, 1, 2, 5, 6]),
+            ("$lt", "2", vec![3, 6]),
+            ("$gt", "a", vec![5]),
+        ] {
+            let query: Query = serde_json::from_value(json!({ "~num": { op: operand } })).unwrap();
+            let evaluated: Vec<usize> = (0..items.len())
+                .filter(|idx| query.matches(&items[*idx]))
+                .collect();
+            assert_eq!(evaluated, expected, "query: {:?}", query);
+            let sql = query
+                .clone()
+                .to_sql::<Value>(&layout, |name| is_plaintext(name))
+                .unwrap();
+            assert_eq!(select(&conn, sql), expected, "query: {:?}", query);
+        }
+
+        // versions are compared by segment in `matches` but as strings in SQL
+        let query: Query = serde_json::from_value(json!({ "~num": { "$gt": "1.9" } })).unwrap();
+        let evaluated: Vec<usize> = (0..items.len())
+            .filter(|idx| query.matches(&items[*idx]))
+            .collect();
+        assert_eq!(evaluated, [0, 1, 2, 5, 6]);
+        let sql = query
+            .to_sql::<Value>(&layout, |name| is_plaintext(name))
+            .unwrap();
+        assert_eq!(select(&conn, sql), [0, 1, 2, 5]);
+    }
+
+    #[test]
+    fn test_sql_placeholders() {
+        let query: Query = serde_json::from_value(json!({
+            "schema_name": "gvt",
+            "~age": {"$in": ["28", "35"]},
+        }))
+        .unwrap();
+        let layout = SqlTagLayout::encrypted("i.id", "items_tags")
+            .with_placeholder(SqlPlaceholder::Numbered { first: 3 });
+        let sql = query
+            .to_sql::<String>(&layout, |name| is_plaintext(name))
+            .unwrap()
+            .unwrap();
+        assert_eq!(sql.params.len(), 5);
+        for idx in 3..=7 {
+            assert!(sql.clause.contains(&format!("${}", idx)));
+        }
+        assert!(!sql.clause.contains("$8"));
+    }
+}
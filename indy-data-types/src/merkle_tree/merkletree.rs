@@ -130,7 +130,7 @@ impl MerkleTree {
     }
 
     /// Creates an `Iterator` over the values contained in this Merkle tree.
-    pub fn iter(&self) -> LeavesIterator {
+    pub fn iter(&self) -> LeavesIterator<'_> {
         self.root.iter()
     }
 }
@@ -87,7 +87,7 @@ impl MerkleTree {
         let mut old_node = self.count - 1;
         let mut new_node = new_size - 1;
 
-        while old_node % 2 != 0 {
+        while old_node % 2 == 1 {
             old_node /= 2;
             new_node /= 2;
         }
@@ -105,7 +105,7 @@ impl MerkleTree {
         }
 
         while old_node != 0 {
-            if old_node % 2 != 0 {
+            if old_node % 2 == 1 {
                 let next_proof = unwrap_opt_or_return!(proofs.next(), Ok(false));
                 old_hash = Hash::hash_nodes(next_proof, &old_hash)?.to_vec();
                 new_hash = Hash::hash_nodes(next_proof, &new_hash)?.to_vec();
@@ -389,7 +389,7 @@ mod tests {
 
     #[test]
     fn consistency_proof_works_for_old4_new8() {
-        let all_str_values = vec![
+        let all_str_values = [
             r#"{"data":{"alias":"Node1","client_ip":"10.0.0.2","client_port":9702,"node_ip":"10.0.0.2","node_port":9701,"services":["VALIDATOR"]},"dest":"Gw6pDLhcBcoQesN72qfotTgFa7cbuqZpkX3Xo6pLhPhv","identifier":"FYmoFw55GeQH7SRFa37dkx1d2dZ3zUF8ckg7wmL7ofN4","txnId":"fea82e10e894419fe2bea7d96296a6d46f50f93f9eeda954ec461b2ed2950b62","type":"0"}"#,
             r#"{"data":{"alias":"Node2","client_ip":"10.0.0.2","client_port":9704,"node_ip":"10.0.0.2","node_port":9703,"services":["VALIDATOR"]},"dest":"8ECVSk179mjsjKRLWiQtssMLgp6EPhWXtaYyStWPSGAb","identifier":"8QhFxKxyaFsJy4CyxeYX34dFH8oWqyBv1P4HLQCsoeLy","txnId":"1ac8aece2a18ced660fef8694b61aac3af08ba875ce3026a160acbc3a3af35fc","type":"0"}"#,
             r#"{"data":{"alias":"Node3","client_ip":"10.0.0.2","client_port":9706,"node_ip":"10.0.0.2","node_port":9705,"services":["VALIDATOR"]},"dest":"DKVxG2fXXTU8yT5N7hGEbXB3dfdAnYv1JczDUHpmDxya","identifier":"2yAeV5ftuasWNgQwVYzeHeTuM7LwwNtPR3Zg9N4JiDgF","txnId":"7e9f355dffa78ed24668f0e0e369fd8c224076571c51e2ea8be5f26479edebe4","type":"0"}"#,
@@ -57,7 +57,7 @@ impl Tree {
     }
 
     /// Returns a borrowing iterator over the leaves of the tree.
-    pub fn iter(&self) -> LeavesIterator {
+    pub fn iter(&self) -> LeavesIterator<'_> {
         LeavesIterator::new(self)
     }
 
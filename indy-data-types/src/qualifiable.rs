@@ -2,7 +2,7 @@ use once_cell::sync::Lazy;
 
 use regex::Regex;
 
-use crate::{invalid, Validatable, ValidationError};
+use crate::{Validatable, ValidationError};
 
 pub(crate) static REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new("^([a-z0-9]+):([a-z0-9]+):(.*)$").unwrap());
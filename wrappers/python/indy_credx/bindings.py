@@ -7,6 +7,7 @@ import sys
 from ctypes import (
     Array,
     CDLL,
+    CFUNCTYPE,
     POINTER,
     Structure,
     addressof,
@@ -17,6 +18,7 @@ from ctypes import (
     c_int8,
     c_int64,
     c_ubyte,
+    memmove,
     pointer,
 )
 from ctypes.util import find_library
@@ -705,6 +707,32 @@ def revoke_credential(
     return upd_rev_reg, rev_delta
 
 
+def unrevoke_credential(
+    cred_def: ObjectHandle,
+    rev_reg_def: ObjectHandle,
+    rev_reg_def_private: ObjectHandle,
+    rev_reg: ObjectHandle,
+    cred_rev_idx: int,
+    issued: Sequence[int],
+    revoked: Sequence[int],
+) -> Tuple[ObjectHandle, ObjectHandle]:
+    upd_rev_reg = ObjectHandle()
+    rev_delta = ObjectHandle()
+    do_call(
+        "credx_unrevoke_credential",
+        cred_def,
+        rev_reg_def,
+        rev_reg_def_private,
+        rev_reg,
+        c_int64(cred_rev_idx),
+        FfiIntList.create(issued),
+        FfiIntList.create(revoked),
+        byref(upd_rev_reg),
+        byref(rev_delta),
+    )
+    return upd_rev_reg, rev_delta
+
+
 def create_credential_offer(
     schema_id: str, cred_def: ObjectHandle, key_proof: ObjectHandle
 ) -> ObjectHandle:
@@ -788,27 +816,45 @@ def verify_presentation(
     rev_reg_defs: Sequence[ObjectHandle],
     rev_regs: Sequence[RevocationEntry],
     accept_legacy_revocation: bool = False,
+    policy: Mapping = None,
 ) -> bool:
     verify = c_int8()
     entry_list = RevocationEntryList()
     if rev_regs:
         entry_list.count = len(rev_regs)
         entry_list.data = (RevocationEntry * entry_list.count)(*rev_regs)
-    do_call(
-        "credx_verify_presentation_legacy"
-        if accept_legacy_revocation
-        else "credx_verify_presentation",
+    args = [
         presentation,
         pres_req,
         FfiObjectHandleList.create(schemas),
         FfiObjectHandleList.create(cred_defs),
         FfiObjectHandleList.create(rev_reg_defs),
         entry_list,
-        byref(verify),
-    )
+    ]
+    if policy is not None:
+        policy = dict(policy)
+        if accept_legacy_revocation:
+            policy["accept_legacy_revocation"] = True
+        do_call(
+            "credx_verify_presentation_with_policy",
+            *args,
+            encode_str(json.dumps(policy)),
+            byref(verify),
+        )
+    else:
+        do_call(
+            "credx_verify_presentation_legacy"
+            if accept_legacy_revocation
+            else "credx_verify_presentation",
+            *args,
+            byref(verify),
+        )
     return bool(verify)
 
 
+TAILS_PROGRESS_CB = CFUNCTYPE(c_int64, c_int64, c_int64, c_int64)
+
+
 def create_revocation_registry(
     origin_did: str,
     cred_def: ObjectHandle,
@@ -817,13 +863,13 @@ def create_revocation_registry(
     issuance_type: Optional[str],
     max_cred_num: int,
     tails_dir_path: Optional[str],
+    progress: Callable[[int, int], Optional[bool]] = None,
 ) -> Tuple[ObjectHandle, ObjectHandle, ObjectHandle, ObjectHandle]:
     reg_def = ObjectHandle()
     reg_def_private = ObjectHandle()
     reg_entry = ObjectHandle()
     reg_init_delta = ObjectHandle()
-    do_call(
-        "credx_create_revocation_registry",
+    args = [
         encode_str(origin_did),
         cred_def,
         encode_str(tag),
@@ -831,6 +877,25 @@ def create_revocation_registry(
         encode_str(issuance_type),
         c_int64(max_cred_num),
         encode_str(tails_dir_path),
+    ]
+    if progress:
+
+        def _progress(_cb_id: int, written: int, total: int) -> int:
+            try:
+                # returning False from the progress callback cancels generation
+                return int(progress(written, total) is False)
+            except Exception:
+                LOGGER.exception("Error in tails progress callback")
+                return 1
+
+        progress_cb = TAILS_PROGRESS_CB(_progress)
+        fn_name = "credx_create_revocation_registry_with_progress"
+        args.extend((progress_cb, c_int64(0)))
+    else:
+        fn_name = "credx_create_revocation_registry"
+    do_call(
+        fn_name,
+        *args,
         byref(reg_def),
         byref(reg_def_private),
         byref(reg_entry),
@@ -877,6 +942,53 @@ def merge_revocation_registry_deltas(
     return rev_delta
 
 
+TAILS_ACCESSOR_CB = CFUNCTYPE(c_int64, c_int64, c_int64, POINTER(c_ubyte), c_int64)
+
+
+def create_or_update_revocation_state_with_accessor(
+    rev_reg_def: ObjectHandle,
+    rev_reg_delta: ObjectHandle,
+    rev_reg_index: int,
+    timestamp: int,
+    tails_accessor: Callable[[int], bytes],
+    prev_rev_state: Optional[ObjectHandle],
+) -> ObjectHandle:
+    def _access_tail(_cb_id: int, tail_id: int, tail_p, tail_len: int) -> int:
+        try:
+            tail = tails_accessor(tail_id)
+        except Exception:
+            LOGGER.exception("Error fetching tail %d", tail_id)
+            return 1
+        if len(tail) != tail_len:
+            LOGGER.error("Invalid length for tail %d: %d", tail_id, len(tail))
+            return 1
+        memmove(tail_p, tail, tail_len)
+        return 0
+
+    tails_cb = TAILS_ACCESSOR_CB(_access_tail)
+    rev_state = ObjectHandle()
+    do_call(
+        "credx_create_or_update_revocation_state_with_accessor",
+        rev_reg_def,
+        rev_reg_delta,
+        c_int64(rev_reg_index),
+        c_int64(timestamp),
+        tails_cb,
+        c_int64(0),
+        prev_rev_state or ObjectHandle(),
+        byref(rev_state),
+    )
+    return rev_state
+
+
+def verify_tails_file(rev_reg_def: ObjectHandle, tails_path: str):
+    do_call(
+        "credx_verify_tails_file",
+        rev_reg_def,
+        encode_str(tails_path),
+    )
+
+
 def create_or_update_revocation_state(
     rev_reg_def: ObjectHandle,
     rev_reg_delta: ObjectHandle,
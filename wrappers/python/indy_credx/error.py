@@ -13,6 +13,9 @@ class CredxErrorCode(IntEnum):
     INVALID_USER_REVOC_ID = 6
     PROOF_REJECTED = 7
     REVOCATION_REGISTRY_FULL = 8
+    INVALID_TAILS_FILE = 9
+    CANCELLED = 10
+    INVALID_NONCE = 11
     WRAPPER = 99
 
 
@@ -1,4 +1,4 @@
-from typing import Mapping, Optional, Sequence, Tuple, Union
+from typing import Callable, Mapping, Optional, Sequence, Tuple, Union
 
 from . import bindings
 from .bindings import JsonType
@@ -432,6 +432,7 @@ class Presentation(bindings.IndyObject):
             str, Mapping[int, Union[JsonType, "RevocationRegistry"]]
         ] = None,
         accept_legacy_revocation: bool = False,
+        policy: Mapping = None,
     ) -> bool:
         if not isinstance(pres_req, bindings.IndyObject):
             pres_req = PresentationRequest.load(pres_req)
@@ -472,6 +473,7 @@ class Presentation(bindings.IndyObject):
             reg_defs,
             reg_entries or None,
             accept_legacy_revocation,
+            policy,
         )
 
 
@@ -489,6 +491,7 @@ class RevocationRegistryDefinition(bindings.IndyObject):
         *,
         issuance_type: str = None,
         tails_dir_path: str = None,
+        progress: Callable[[int, int], Optional[bool]] = None,
     ) -> Tuple[
         "RevocationRegistryDefinition",
         "RevocationRegistryDefinitionPrivate",
@@ -510,6 +513,7 @@ class RevocationRegistryDefinition(bindings.IndyObject):
             issuance_type,
             max_cred_num,
             tails_dir_path,
+            progress,
         )
         return (
             RevocationRegistryDefinition(reg_def),
@@ -568,6 +572,9 @@ class RevocationRegistryDefinition(bindings.IndyObject):
             )
         )
 
+    def verify_tails_file(self, tails_path: str):
+        bindings.verify_tails_file(self.handle, tails_path)
+
 
 class RevocationRegistryDefinitionPrivate(bindings.IndyObject):
     @classmethod
@@ -600,6 +607,28 @@ class RevocationRegistry(bindings.IndyObject):
         )
         return RevocationRegistryDelta(rev_delta)
 
+    def unrevoke_credential(
+        self,
+        cred_def: Union[JsonType, CredentialDefinition],
+        rev_reg_def: Union[JsonType, RevocationRegistryDefinition],
+        rev_reg_def_private: Union[JsonType, RevocationRegistryDefinitionPrivate],
+        cred_rev_idx: int,
+        issued: Sequence[int],
+        revoked: Sequence[int],
+    ) -> "RevocationRegistryDelta":
+        if not isinstance(rev_reg_def, bindings.IndyObject):
+            rev_reg_def = RevocationRegistryDefinition.load(rev_reg_def)
+        self.handle, rev_delta = bindings.unrevoke_credential(
+            cred_def.handle,
+            rev_reg_def.handle,
+            rev_reg_def_private.handle,
+            self.handle,
+            cred_rev_idx,
+            issued,
+            revoked,
+        )
+        return RevocationRegistryDelta(rev_delta)
+
     def update(
         self,
         cred_def: Union[JsonType, CredentialDefinition],
@@ -679,14 +708,25 @@ class CredentialRevocationState(bindings.IndyObject):
         rev_reg_delta: Union[JsonType, RevocationRegistryDelta],
         cred_rev_id: int,
         timestamp: int,
-        tails_path: str,
+        tails_path: str = None,
+        *,
+        tails_accessor: Callable[[int], bytes] = None,
     ) -> "CredentialRevocationState":
         if not isinstance(rev_reg_def, bindings.IndyObject):
             rev_reg_def = RevocationRegistryDefinition.load(rev_reg_def)
         if not isinstance(rev_reg_delta, bindings.IndyObject):
             rev_reg_delta = RevocationRegistryDelta.load(rev_reg_delta)
-        return CredentialRevocationState(
-            bindings.create_or_update_revocation_state(
+        if tails_accessor:
+            handle = bindings.create_or_update_revocation_state_with_accessor(
+                rev_reg_def.handle,
+                rev_reg_delta.handle,
+                cred_rev_id,
+                timestamp,
+                tails_accessor,
+                None,
+            )
+        else:
+            handle = bindings.create_or_update_revocation_state(
                 rev_reg_def.handle,
                 rev_reg_delta.handle,
                 cred_rev_id,
@@ -694,7 +734,7 @@ class CredentialRevocationState(bindings.IndyObject):
                 tails_path,
                 None,
             )
-        )
+        return CredentialRevocationState(handle)
 
     @classmethod
     def load(cls, value: JsonType) -> "CredentialRevocationState":
@@ -708,17 +748,29 @@ class CredentialRevocationState(bindings.IndyObject):
         rev_reg_delta: Union[JsonType, RevocationRegistryDelta],
         rev_reg_index: int,
         timestamp: int,
-        tails_path: str,
+        tails_path: str = None,
+        *,
+        tails_accessor: Callable[[int], bytes] = None,
     ):
         if not isinstance(rev_reg_def, bindings.IndyObject):
             rev_reg_def = RevocationRegistryDefinition.load(rev_reg_def)
         if not isinstance(rev_reg_delta, bindings.IndyObject):
             rev_reg_delta = RevocationRegistryDelta.load(rev_reg_delta)
-        self.handle = bindings.create_or_update_revocation_state(
-            rev_reg_def.handle,
-            rev_reg_delta.handle,
-            rev_reg_index,
-            timestamp,
-            tails_path,
-            self.handle,
-        )
+        if tails_accessor:
+            self.handle = bindings.create_or_update_revocation_state_with_accessor(
+                rev_reg_def.handle,
+                rev_reg_delta.handle,
+                rev_reg_index,
+                timestamp,
+                tails_accessor,
+                self.handle,
+            )
+        else:
+            self.handle = bindings.create_or_update_revocation_state(
+                rev_reg_def.handle,
+                rev_reg_delta.handle,
+                rev_reg_index,
+                timestamp,
+                tails_path,
+                self.handle,
+            )